@@ -1 +1,3 @@
-pub mod convert;
\ No newline at end of file
+#[allow(clippy::module_inception)]
+pub mod convert;
+pub mod citation;
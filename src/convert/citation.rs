@@ -0,0 +1,228 @@
+use crate::parser::parser::Cite;
+use crate::parser::bibtex::Entry;
+use super::convert::escape;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub enum CitationStyle {
+    /// `(Doe & Roe, 2020, p. 4)`; references as `Doe, J., & Roe, R. (2020). Title.`
+    #[default]
+    Apa,
+    /// Chicago author-date: `(Doe and Roe 2020, p. 4)`; references as
+    /// `Doe, Jane and Richard Roe. 2020. “Title.”`
+    Chicago,
+}
+
+struct Name {
+    first: String,
+    last: String,
+}
+
+fn name(text: &str) -> Name {
+    if let Some((last, first)) = text.split_once(',') {
+        return Name { first: first.trim().to_string(), last: last.trim().to_string() }
+    }
+    match text.trim().rsplit_once(' ') {
+        Some((first, last)) => Name { first: first.trim().to_string(), last: last.to_string() },
+        None => Name { first: "".to_string(), last: text.trim().to_string() },
+    }
+}
+
+fn names(entry: &Entry) -> Vec<Name> {
+    let authors = entry.field("author").or(entry.field("editor")).unwrap_or("");
+    authors.split(" and ").filter(|author| !author.trim().is_empty()).map(name).collect()
+}
+
+fn initials(first: &str) -> String {
+    let initials: Vec<String> = first.split_whitespace()
+        .filter_map(|part| part.chars().next())
+        .map(|c| format!("{}.", c))
+        .collect();
+    initials.join(" ")
+}
+
+fn year(entry: &Entry) -> &str {
+    entry.field("year").unwrap_or("n.d.")
+}
+
+/// `a`, `a and b`, or `a, b and c` with an optional serial comma.
+fn join(items: Vec<String>, and: &str, serial: bool) -> String {
+    match items.len() {
+        0 => "".to_string(),
+        1 => items[0].clone(),
+        2 if !serial => format!("{} {} {}", items[0], and, items[1]),
+        n => format!("{}, {} {}", items[..(n-1)].join(", "), and, items[n-1]),
+    }
+}
+
+fn sentence(text: &str) -> String {
+    if text.ends_with(['.', '?', '!']) { text.to_string() } else { format!("{}.", text) }
+}
+
+fn author_label(entry: &Entry, style: CitationStyle) -> String {
+    let names = names(entry);
+    let and = match style {
+        CitationStyle::Apa => "&",
+        CitationStyle::Chicago => "and",
+    };
+    match names.as_slice() {
+        [] => entry.field("title").unwrap_or(&entry.key).to_string(),
+        [one] => one.last.clone(),
+        [one, two] => format!("{} {} {}", one.last, and, two.last),
+        [one, ..] => format!("{} et al.", one.last),
+    }
+}
+
+fn cite_text(cite: &Cite, entry: Option<&Entry>, style: CitationStyle) -> String {
+    let Some(entry) = entry else { return format!("{}?", cite.key) };
+    let separator = match style {
+        CitationStyle::Apa => ", ",
+        CitationStyle::Chicago => " ",
+    };
+    let mut text = if cite.suppress_author {
+        year(entry).to_string()
+    } else {
+        format!("{}{}{}", author_label(entry, style), separator, year(entry))
+    };
+    if let Some(locator) = &cite.locator {
+        text.push_str(&format!(", {}", locator));
+    }
+    text
+}
+
+fn find<'a>(entries: &'a [Entry], key: &str) -> Option<&'a Entry> {
+    entries.iter().find(|entry| entry.key == key)
+}
+
+/// The in-text form of a citation, as plain text.
+pub fn citation_text(cites: &[Cite], in_text: bool, entries: &[Entry], style: CitationStyle) -> String {
+    if let (true, [cite]) = (in_text, cites) {
+        return match find(entries, &cite.key) {
+            Some(entry) => format!("{} ({})", author_label(entry, style), year(entry)),
+            None => format!("{}?", cite.key),
+        }
+    }
+    let cites: Vec<String> = cites.iter().map(|cite| cite_text(cite, find(entries, &cite.key), style)).collect();
+    format!("({})", cites.join("; "))
+}
+
+fn authors(entry: &Entry, style: CitationStyle) -> String {
+    let names = names(entry);
+    let serial = names.len() > 2;
+    match style {
+        CitationStyle::Apa => {
+            let names = names.iter().map(|name| match name.first.as_str() {
+                "" => name.last.clone(),
+                first => format!("{}, {}", name.last, initials(first)),
+            });
+            join(names.collect(), "&", true)
+        },
+        CitationStyle::Chicago => {
+            let names = names.iter().enumerate().map(|(i, name)| match (i, name.first.as_str()) {
+                (_, "") => name.last.clone(),
+                (0, first) => format!("{}, {}", name.last, first),
+                (_, first) => format!("{} {}", first, name.last),
+            });
+            join(names.collect(), "and", serial)
+        },
+    }
+}
+
+/// One bibliography entry as HTML.
+pub fn reference(entry: &Entry, style: CitationStyle) -> String {
+    let authors = escape(&authors(entry, style));
+    let title = escape(entry.field("title").unwrap_or(&entry.key));
+    let year = escape(year(entry));
+    let article = entry.kind == "article" || entry.kind == "inproceedings";
+    let container = match article {
+        true => entry.field("journal").or(entry.field("booktitle")),
+        false => entry.field("publisher"),
+    };
+    let title = match (article, style) {
+        (true, CitationStyle::Apa) => sentence(&title),
+        (true, CitationStyle::Chicago) => format!("\u{201C}{}\u{201D}", sentence(&title)),
+        (false, _) => format!("<i>{}</i>.", title),
+    };
+    let container = container.map(|container| match article {
+        true => format!(" <i>{}</i>.", escape(container)),
+        false => format!(" {}", sentence(&escape(container))),
+    })
+    .unwrap_or_default();
+    match style {
+        CitationStyle::Apa if authors.is_empty() => format!("{} ({}).{}", title, year, container),
+        CitationStyle::Apa => format!("{} ({}). {}{}", authors, year, title, container),
+        CitationStyle::Chicago if authors.is_empty() => format!("{} {}.{}", title, year, container),
+        CitationStyle::Chicago => format!("{} {}. {}{}", sentence(&authors), year, title, container),
+    }
+}
+
+/// The references section for the cited `keys` that `entries` defines,
+/// sorted by first author and year. Empty when none are known.
+pub fn bibliography(keys: &[String], entries: &[Entry], style: CitationStyle) -> String {
+    let mut cited: Vec<&Entry> = keys.iter().filter_map(|key| find(entries, key)).collect();
+    if cited.is_empty() { return "".to_string() }
+    cited.sort_by_key(|entry| (author_label(entry, style), year(entry).to_string()));
+    let items: Vec<String> = cited.iter()
+        .map(|entry| format!("<p id=\"ref-{}\">{}</p>", escape(&entry.key), reference(entry, style)))
+        .collect();
+    format!("<div id=\"refs\" class=\"references\">\n{}\n</div>\n", items.join("\n"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(kind: &str, key: &str, fields: &[(&str, &str)]) -> Entry {
+        let fields = fields.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect();
+        Entry { kind: kind.to_string(), key: key.to_string(), fields }
+    }
+
+    fn entries() -> Vec<Entry> {
+        vec!(
+            entry("article", "doe", &[
+                ("author", "Doe, Jane and Richard Roe"),
+                ("title", "On Parsing"),
+                ("journal", "J. Markup"),
+                ("year", "2020"),
+            ]),
+            entry("book", "abbot", &[
+                ("author", "Abbot, Ann and Bo Bee and Cy Sea"),
+                ("title", "Big Book"),
+                ("publisher", "Press"),
+                ("year", "1999"),
+            ]),
+        )
+    }
+
+    fn cite(key: &str, locator: Option<&str>, suppress_author: bool) -> Cite {
+        Cite { key: key.to_string(), locator: locator.map(|l| l.to_string()), suppress_author }
+    }
+
+    #[test]
+    fn test_citation_text() {
+        let entries = entries();
+        let cites = vec!(cite("doe", Some("p. 4"), false), cite("abbot", None, true), cite("nope", None, false));
+        assert_eq!(citation_text(&cites, false, &entries, CitationStyle::Apa), "(Doe & Roe, 2020, p. 4; 1999; nope?)");
+        assert_eq!(citation_text(&cites, false, &entries, CitationStyle::Chicago), "(Doe and Roe 2020, p. 4; 1999; nope?)");
+        assert_eq!(citation_text(&[cite("abbot", None, false)], true, &entries, CitationStyle::Apa), "Abbot et al. (1999)");
+        assert_eq!(citation_text(&[cite("nope", None, false)], true, &entries, CitationStyle::Apa), "nope?");
+    }
+
+    #[test]
+    fn test_reference() {
+        let entries = entries();
+        assert_eq!(reference(&entries[0], CitationStyle::Apa), "Doe, J., &amp; Roe, R. (2020). On Parsing. <i>J. Markup</i>.");
+        assert_eq!(reference(&entries[0], CitationStyle::Chicago), "Doe, Jane and Richard Roe. 2020. \u{201C}On Parsing.\u{201D} <i>J. Markup</i>.");
+        assert_eq!(reference(&entries[1], CitationStyle::Apa), "Abbot, A., Bee, B., &amp; Sea, C. (1999). <i>Big Book</i>. Press.");
+        assert_eq!(reference(&entries[1], CitationStyle::Chicago), "Abbot, Ann, Bo Bee, and Cy Sea. 1999. <i>Big Book</i>. Press.");
+    }
+
+    #[test]
+    fn test_bibliography() {
+        let entries = entries();
+        let keys = vec!("doe".to_string(), "nope".to_string(), "abbot".to_string());
+        let html = bibliography(&keys, &entries, CitationStyle::Apa);
+        assert!(html.starts_with("<div id=\"refs\" class=\"references\">\n<p id=\"ref-abbot\">"));
+        assert!(html.ends_with("<p id=\"ref-doe\">Doe, J., &amp; Roe, R. (2020). On Parsing. <i>J. Markup</i>.</p>\n</div>\n"));
+        assert_eq!(bibliography(&["nope".to_string()], &entries, CitationStyle::Apa), "".to_string());
+    }
+}
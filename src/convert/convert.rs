@@ -1,26 +1,174 @@
+use std::collections::HashMap;
 use crate::parser::parser::*;
+use crate::parser::toc::{toc, TocEntry, TocOptions};
+use crate::parser::bibtex::Entry;
+use crate::parser::citation::keys;
+use super::citation::{CitationStyle, citation_text, bibliography};
+
+pub trait WikiResolver {
+    /// Returns the URL of `page`, or `None` when the page does not exist.
+    fn resolve(&self, page: &str) -> Option<String>;
+}
+
+pub trait DirectiveRenderer {
+    /// Renders a `:::name` container, or one a registered `BlockParser`
+    /// produced, whose children are already rendered as `body`.
+    fn render(&self, attrs: &Attributes, body: &str) -> String;
+}
+
+pub trait InlineRenderer {
+    /// Renders a `Word::Extension` whose words are already rendered as `body`.
+    fn render(&self, body: &str) -> String;
+}
+
+pub enum Transformed {
+    /// Raw HTML or SVG, emitted as is.
+    Html(String),
+    /// Blocks rendered in place of the code block.
+    Blocks(Vec<Md>),
+}
+
+pub trait CodeTransformer {
+    /// Rewrites a fenced code block, or returns `None` to render it as usual.
+    fn transform(&self, block: &CodeBlock) -> Option<Transformed>;
+}
+
+/// Languages rendered as `<pre class="lang">` for client-side diagram
+/// libraries when no transformer is registered.
+const DIAGRAMS: [&str; 4] = ["mermaid", "dot", "graphviz", "plantuml"];
+
+#[derive(Default)]
+pub struct ConvertOptions {
+    pub toc: TocOptions,
+    pub wiki: Option<Box<dyn WikiResolver>>,
+    /// Renderers for `:::name` containers, keyed by name.
+    pub directives: HashMap<String, Box<dyn DirectiveRenderer>>,
+    /// Renderers for `Word::Extension` words, keyed by name.
+    pub inlines: HashMap<String, Box<dyn InlineRenderer>>,
+    /// Transformers for fenced code blocks, keyed by language.
+    pub code: HashMap<String, Box<dyn CodeTransformer>>,
+    /// Entries cited by `[@key]`; see `parser::bibtex::read`.
+    pub bibliography: Vec<Entry>,
+    pub citation_style: CitationStyle,
+}
 
-fn word_to_html<'a>(word: &'a Word) -> String {
+struct Context<'a> {
+    opts: &'a ConvertOptions,
+    toc: Vec<TocEntry>,
+    /// Labels of the rendered footnotes, in the order they are numbered.
+    footnotes: Vec<String>,
+}
+
+impl<'a> Context<'a> {
+    fn new(opts: &'a ConvertOptions) -> Context<'a> {
+        Context { opts, toc: vec!(), footnotes: vec!() }
+    }
+}
+
+fn word_to_html(word: &Word, ctx: &Context) -> String {
     match word {
         Word::Normal(val) => val.clone(),
-        Word::Italic(words) => format!("<i>{}</i>", words_to_html(&words)),
-        Word::Bold(words) => format!("<b>{}</b>", words_to_html(&words)),
-        Word::StrikeThough(words) => format!("<s>{}</s>", words_to_html(&words)),
-        Word::Underline(words) => format!("<u>{}</u>", words_to_html(&words)),
+        Word::Italic(words) => format!("<i>{}</i>", words_to_html(words, ctx)),
+        Word::Bold(words) => format!("<b>{}</b>", words_to_html(words, ctx)),
+        Word::StrikeThough(words) => format!("<s>{}</s>", words_to_html(words, ctx)),
+        Word::Underline(words) => format!("<u>{}</u>", words_to_html(words, ctx)),
+        Word::Highlight(words) => format!("<mark>{}</mark>", words_to_html(words, ctx)),
+        Word::Superscript(words) => format!("<sup>{}</sup>", words_to_html(words, ctx)),
+        Word::Subscript(words) => format!("<sub>{}</sub>", words_to_html(words, ctx)),
+        Word::Code(code) => format!("<code>{}</code>", escape(code)),
+        Word::Emoji { char, .. } => char.clone(),
+        Word::WikiLink { page, label } => wiki_link_to_html(page, label, ctx),
+        Word::Link { words, url, title, attrs } => {
+            let title = title_to_html(title);
+            let attrs = attributes_to_html(attrs);
+            format!("<a href=\"{}\"{}{}>{}</a>", escape(url), title, attrs, words_to_html(words, ctx))
+        },
+        Word::Image { alt, url, title, attrs } => {
+            let title = title_to_html(title);
+            let attrs = attributes_to_html(attrs);
+            format!("<img src=\"{}\" alt=\"{}\"{}{} />", escape(url), escape(alt), title, attrs)
+        },
+        Word::Span { words, attrs } => {
+            format!("<span{}>{}</span>", attributes_to_html(attrs), words_to_html(words, ctx))
+        },
+        Word::Citation { cites, in_text } => citation_to_html(cites, *in_text, ctx),
+        Word::Extension { name, words } => extension_to_html(name, words, ctx),
+        Word::Footnote { label } => footnote_ref_to_html(label, ctx),
+        Word::Abbreviation { abbr, title } => {
+            format!("<abbr title=\"{}\">{}</abbr>", escape(title), escape(abbr))
+        },
+    }
+}
+
+fn footnote_ref_to_html(label: &str, ctx: &Context) -> String {
+    let Some(n) = ctx.footnotes.iter().position(|l| l == label) else {
+        return format!("[^{}]", escape(label))
+    };
+    let label = escape(label);
+    format!("<sup class=\"footnote-ref\"><a href=\"#fn-{}\" id=\"fnref-{}\">{}</a></sup>", label, label, n + 1)
+}
+
+fn extension_to_html(name: &str, words: &Words, ctx: &Context) -> String {
+    let body = words_to_html(words, ctx);
+    match ctx.opts.inlines.get(name) {
+        Some(renderer) => renderer.render(&body),
+        None => format!("<span class=\"{}\">{}</span>", escape(name), body),
+    }
+}
+
+fn citation_to_html(cites: &[Cite], in_text: bool, ctx: &Context) -> String {
+    let entries = &ctx.opts.bibliography;
+    let text = citation_text(cites, in_text, entries, ctx.opts.citation_style);
+    let keys: Vec<&str> = cites.iter().map(|cite| cite.key.as_str()).collect();
+    let known = cites.iter().all(|cite| entries.iter().any(|entry| entry.key == cite.key));
+    let class = if known { "citation" } else { "citation unknown" };
+    format!("<span class=\"{}\" data-cites=\"{}\">{}</span>", class, escape(&keys.join(" ")), escape(&text))
+}
+
+fn title_to_html(title: &Option<String>) -> String {
+    match title {
+        Some(title) => format!(" title=\"{}\"", escape(title)),
+        None => "".to_string(),
+    }
+}
+
+fn attributes_to_html(attrs: &Attributes) -> String {
+    let mut html = String::new();
+    if let Some(id) = &attrs.id {
+        html.push_str(&format!(" id=\"{}\"", escape(id)));
+    }
+    if !attrs.classes.is_empty() {
+        html.push_str(&format!(" class=\"{}\"", escape(&attrs.classes.join(" "))));
+    }
+    for (key, value) in attrs.pairs.iter() {
+        html.push_str(&format!(" {}=\"{}\"", escape(key), escape(value)));
     }
+    html
 }
 
-fn words_to_html<'a>(words: &'a Words) -> String {
+fn wiki_link_to_html(page: &str, label: &Option<String>, ctx: &Context) -> String {
+    let label = label.as_deref().unwrap_or(page);
+    let url = match &ctx.opts.wiki {
+        Some(resolver) => resolver.resolve(page),
+        None => Some(page.to_string()),
+    };
+    match url {
+        Some(url) => format!("<a href=\"{}\">{}</a>", escape(&url), label),
+        None => format!("<a class=\"broken\" title=\"{}\">{}</a>", escape(page), label),
+    }
+}
+
+fn words_to_html(words: &Words, ctx: &Context) -> String {
     let words = &words.0;
-    words.iter().map(|word| word_to_html(word))
+    words.iter().map(|word| word_to_html(word, ctx))
         .collect::<Vec<String>>()
         .join("")
 }
 
-fn header_to_html(record: &Record) -> String {
+fn header_to_html(record: &Record, ctx: &Context) -> String {
     let header = &record.0;
     header.iter().map(
-        |words| format!("<th>{}</th>", words_to_html(words))
+        |words| format!("<th>{}</th>", words_to_html(words, ctx))
     )
     .collect::<Vec<String>>()
     .join("")
@@ -34,78 +182,263 @@ fn align_to_string<'a>(align: &Align) -> &'a str {
     }
 }
 
-fn record_to_html(record: &Record, aligns: &Vec<Align>) -> String {
+fn record_to_html(record: &Record, aligns: &[Align], ctx: &Context) -> String {
     let record = &record.0;
     record.iter().zip(aligns.iter()).map(
         |(words, align)| {
             let align = align_to_string(align);
-            format!("<td align=\"{}\">{}</td>", align, words_to_html(words))
+            format!("<td align=\"{}\">{}</td>", align, words_to_html(words, ctx))
     })
     .collect::<Vec<String>>()
     .join("")
 }
 
-fn records_to_html(records: &Vec<Record>, aligns: &Vec<Align>) -> String {
+fn records_to_html(records: &[Record], aligns: &[Align], ctx: &Context) -> String {
     records.iter().map(|record| {
-        format!("<tr>{}</tr>\n", record_to_html(record, aligns))
+        format!("<tr>{}</tr>\n", record_to_html(record, aligns, ctx))
     })
     .collect::<Vec<String>>()
     .join("")
 }
 
-fn table_to_html(table: &Box<Table>) -> String {
+fn table_to_html(table: &Table, ctx: &Context) -> String {
     let header = &table.header;
     let aligns = &table.align;
     let records = &table.records;
 
-    let header = header_to_html(header);
+    let header = header_to_html(header, ctx);
     let header = format!("<tr>{}</tr>", header);
-    let records = records_to_html(records, aligns);
+    let records = records_to_html(records, aligns, ctx);
     format!("<table>\n{}\n{}</table>\n", header, records)
 }
 
-fn item_to_html(item: &Item) -> String {
-    let words = &item.0;
-    let words = words_to_html(words);
-    let children = &item.1;
-    let children = if children.0.is_empty() {
-        "".to_string()
-    } else {
-        format!("\n{}", items_to_html(children))
+/// Blocks inside a list item or footnote, where sentences are paragraphs
+/// only when `loose`.
+/// Definitions render nothing in place, so they are left out before
+/// blocks are joined.
+fn is_definition(md: &Md) -> bool {
+    matches!(md, Md::Abbreviation { .. } | Md::Footnote { .. })
+}
+
+fn inner_blocks_to_html(blocks: &[Md], loose: bool, ctx: &Context) -> String {
+    let blocks: Vec<String> = blocks.iter().filter(|md| !is_definition(md)).map(|md| match md {
+        Md::Sentence(words) if loose => format!("<p>{}</p>", words_to_html(words, ctx)),
+        Md::Sentence(words) => words_to_html(words, ctx),
+        md => md_to_html(md, ctx),
+    }).collect();
+    blocks.join("\n")
+}
+
+fn item_to_html(item: &Item, loose: bool, ctx: &Context) -> String {
+    let checkbox = match item.1 {
+        Some(true) => "<input type=\"checkbox\" disabled=\"\" checked=\"\" /> ",
+        Some(false) => "<input type=\"checkbox\" disabled=\"\" /> ",
+        None => "",
     };
-    format!("<li>{}{}</li>", words, children)
+    format!("<li>{}{}</li>", checkbox, inner_blocks_to_html(&item.0, loose, ctx))
 }
 
-fn items_to_html(items: &Items) -> String {
-    let items = &items.0;
-    let strings: Vec<String> = items.iter().map(|item| item_to_html(item)).collect();
+fn list_to_html(list: &List, ctx: &Context) -> String {
+    let items = &list.items.0;
+    let strings: Vec<String> = items.iter().map(|item| item_to_html(item, list.loose, ctx)).collect();
     let html = strings.join("\n");
     format!("<ul>\n{}\n</ul>\n", html)
 }
 
-fn heading_to_html(size: &usize, words: &Words) -> String {
-    format!("<h{}>{}</h{}>", size, words_to_html(&words), size)
+pub(crate) fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
 }
 
-fn sentence_to_html(words: &Words) -> String {
-    format!("{}<br />", words_to_html(&words))
+fn heading_to_html(heading: &Heading, ctx: &Context) -> String {
+    let size = heading.level;
+    let id = escape(&heading.id);
+    let attrs = attributes_to_html(&heading.attrs);
+    format!("<h{} id=\"{}\"{}>{}</h{}>", size, id, attrs, words_to_html(&heading.words, ctx), size)
 }
 
-fn md_to_html(md: &Md) -> String {
-    match md {
-        Md::Heading(size, words) => heading_to_html(size, words),
-        Md::Sentence(words) => sentence_to_html(&words),
-        Md::Table(table) => table_to_html(&table),
-        Md::List(items) => items_to_html(&items),
+fn code_block_to_html(block: &CodeBlock, ctx: &Context) -> String {
+    let transformed = ctx.opts.code.get(&block.lang).and_then(|transformer| transformer.transform(block));
+    match transformed {
+        Some(Transformed::Html(html)) => return html,
+        Some(Transformed::Blocks(mds)) => return blocks_to_html(&mds, ctx),
+        None => {},
+    }
+    if DIAGRAMS.contains(&block.lang.as_str()) {
+        let mut attrs = block.attrs.clone();
+        attrs.classes.insert(0, block.lang.clone());
+        return format!("<pre{}>{}</pre>\n", attributes_to_html(&attrs), escape(&block.code))
     }
+    let attrs = attributes_to_html(&block.attrs);
+    let lang = if block.lang.is_empty() {
+        "".to_string()
+    } else {
+        format!(" class=\"language-{}\"", escape(&block.lang))
+    };
+    format!("<pre{}><code{}>{}</code></pre>\n", attrs, lang, escape(&block.code))
+}
+
+fn sentence_to_html(words: &Words, ctx: &Context) -> String {
+    format!("{}<br />", words_to_html(words, ctx))
+}
+
+fn toc_entries_to_html(entries: &[TocEntry]) -> String {
+    let items: Vec<String> = entries.iter().map(|entry| {
+        let children = if entry.children.is_empty() {
+            "".to_string()
+        } else {
+            format!("\n{}", toc_entries_to_html(&entry.children))
+        };
+        format!("<li><a href=\"#{}\">{}</a>{}</li>", escape(&entry.id), escape(&entry.text), children)
+    }).collect();
+    format!("<ul>\n{}\n</ul>\n", items.join("\n"))
+}
+
+pub fn toc_to_html(entries: &[TocEntry]) -> String {
+    if entries.is_empty() { return "<nav class=\"toc\"></nav>\n".to_string() }
+    format!("<nav class=\"toc\">\n{}</nav>\n", toc_entries_to_html(entries))
 }
 
-pub fn mds_to_html(mds: &Vec<Md>) -> String {
-    let strings = mds.iter().map(|md| md_to_html(md));
-    let strings: Vec<String> = strings.collect();
+/// `text` with the characters that would end a link's text escaped.
+fn escape_link_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('[', "\\[")
+        .replace(']', "\\]")
+}
+
+fn toc_entries_to_markdown(entries: &[TocEntry], depth: usize) -> Vec<String> {
+    entries.iter().flat_map(|entry| {
+        let line = format!("{}- [{}](#{})", "  ".repeat(depth), escape_link_text(&entry.text), entry.id);
+        let mut lines = vec!(line);
+        lines.extend(toc_entries_to_markdown(&entry.children, depth + 1));
+        lines
+    }).collect()
+}
+
+pub fn toc_to_markdown(entries: &[TocEntry]) -> String {
+    toc_entries_to_markdown(entries, 0).join("\n")
+}
+
+fn blocks_to_html(mds: &[Md], ctx: &Context) -> String {
+    let strings: Vec<String> = mds.iter().filter(|md| !is_definition(md)).map(|md| md_to_html(md, ctx)).collect();
     strings.join("\n")
 }
 
+fn quote_to_html(body: &[Md], ctx: &Context) -> String {
+    format!("<blockquote>\n{}\n</blockquote>\n", blocks_to_html(body, ctx))
+}
+
+fn admonition_kind_to_string<'a>(kind: &AdmonitionKind) -> (&'a str, &'a str) {
+    match kind {
+        AdmonitionKind::Note => ("note", "Note"),
+        AdmonitionKind::Tip => ("tip", "Tip"),
+        AdmonitionKind::Important => ("important", "Important"),
+        AdmonitionKind::Warning => ("warning", "Warning"),
+        AdmonitionKind::Caution => ("caution", "Caution"),
+    }
+}
+
+fn admonition_to_html(kind: &AdmonitionKind, title: &Option<Words>, body: &[Md], ctx: &Context) -> String {
+    let (class, label) = admonition_kind_to_string(kind);
+    let title = title.as_ref().map(|title| words_to_html(title, ctx)).unwrap_or(label.to_string());
+    let title = format!("<p class=\"admonition-title\">{}</p>", title);
+    let body = blocks_to_html(body, ctx);
+    format!("<div class=\"admonition {}\">\n{}\n{}\n</div>\n", class, title, body)
+}
+
+fn container_to_html(name: &str, attrs: &Attributes, body: &[Md], ctx: &Context) -> String {
+    let body = blocks_to_html(body, ctx);
+    if let Some(renderer) = ctx.opts.directives.get(name) {
+        return renderer.render(attrs, &body)
+    }
+    let mut attrs = attrs.clone();
+    if !name.is_empty() {
+        attrs.classes.insert(0, name.to_string());
+    }
+    format!("<div{}>\n{}\n</div>\n", attributes_to_html(&attrs), body)
+}
+
+fn details_to_html(summary: &Words, open: bool, body: &[Md], ctx: &Context) -> String {
+    let open = if open { " open" } else { "" };
+    let summary = words_to_html(summary, ctx);
+    format!("<details{}>\n<summary>{}</summary>\n{}\n</details>\n", open, summary, blocks_to_html(body, ctx))
+}
+
+fn md_to_html(md: &Md, ctx: &Context) -> String {
+    match md {
+        Md::Heading(heading) => heading_to_html(heading, ctx),
+        Md::Sentence(words) => sentence_to_html(words, ctx),
+        Md::Table(table) => table_to_html(table, ctx),
+        Md::List(list) => list_to_html(list, ctx),
+        Md::Toc(_) => toc_to_html(&ctx.toc),
+        Md::Code(block) => code_block_to_html(block, ctx),
+        Md::Quote(body, _) => quote_to_html(body, ctx),
+        Md::Admonition { kind, title, body, .. } => admonition_to_html(kind, title, body, ctx),
+        Md::Abbreviation { .. } | Md::Footnote { .. } => "".to_string(),
+        Md::Container { name, attrs, body, .. } => container_to_html(name, attrs, body, ctx),
+        Md::Details { summary, open, body, .. } => details_to_html(summary, *open, body, ctx),
+    }
+}
+
+fn footnote_definitions<'a>(mds: &'a [Md], found: &mut Vec<(&'a str, &'a [Md])>) {
+    for md in mds {
+        if let Md::Footnote { label, body, .. } = md {
+            found.push((label, body));
+        }
+        for body in md.children() {
+            footnote_definitions(body, found);
+        }
+    }
+}
+
+/// The defined footnotes in the order they are first referenced.
+fn footnotes(mds: &[Md]) -> Vec<(&str, &[Md])> {
+    let mut definitions: Vec<(&str, &[Md])> = vec!();
+    footnote_definitions(mds, &mut definitions);
+    let mut labels: Vec<String> = vec!();
+    walk_words(mds, &mut |words| {
+        for word in words.0.iter() {
+            let Word::Footnote { label } = word else { continue };
+            if !labels.contains(label) {
+                labels.push(label.clone());
+            }
+        }
+    });
+    labels.iter()
+        .filter_map(|label| definitions.iter().find(|(defined, _)| defined == label).copied())
+        .collect()
+}
+
+fn footnotes_to_html(footnotes: &[(&str, &[Md])], ctx: &Context) -> String {
+    if footnotes.is_empty() { return "".to_string() }
+    let items: Vec<String> = footnotes.iter().map(|(label, body)| {
+        let label = escape(label);
+        let back = format!("<a href=\"#fnref-{}\" class=\"footnote-backref\">↩</a>", label);
+        format!("<li id=\"fn-{}\">{} {}</li>", label, inner_blocks_to_html(body, false, ctx), back)
+    }).collect();
+    format!("<section class=\"footnotes\">\n<ol>\n{}\n</ol>\n</section>\n", items.join("\n"))
+}
+
+pub fn mds_to_html(mds: &[Md]) -> String {
+    mds_to_html_with(mds, &ConvertOptions::default())
+}
+
+pub fn mds_to_html_with(mds: &[Md], opts: &ConvertOptions) -> String {
+    let footnotes = footnotes(mds);
+    let labels = footnotes.iter().map(|(label, _)| label.to_string()).collect();
+    let ctx = Context { toc: toc(mds, &opts.toc), footnotes: labels, ..Context::new(opts) };
+    let mut html = blocks_to_html(mds, &ctx);
+    let references = bibliography(&keys(mds), &opts.bibliography, opts.citation_style);
+    for section in [footnotes_to_html(&footnotes, &ctx), references] {
+        if !section.is_empty() {
+            html = format!("{}\n{}", html, section);
+        }
+    }
+    html
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -114,156 +447,451 @@ mod tests {
     #[test]
     fn test_mds_to_html() {
         let words = words!(normal_word!("Heading"));
-        let heading = Md::Heading(1, words);
+        let heading = Md::Heading(Heading { level: 1, words, id: "heading".to_string(), attrs: Attributes::default(), span: Span::default() });
         let words = words!(normal_word!("Hello"));
         let hello_sentence = Md::Sentence(words);
         let words = words!(normal_word!("World"));
         let world_sentence = Md::Sentence(words);
 
         let mds = vec!(heading, hello_sentence, world_sentence);
-        assert_eq!(mds_to_html(&mds), "<h1>Heading</h1>\nHello<br />\nWorld<br />".to_string());
+        assert_eq!(mds_to_html(&mds), "<h1 id=\"heading\">Heading</h1>\nHello<br />\nWorld<br />".to_string());
     }
 
     #[test]
     fn test_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let words = words!(normal_word!("Hello"));
-        let md = Md::Heading(1, words);
-        assert_eq!(md_to_html(&md), "<h1>Hello</h1>".to_string());
+        let md = Md::Heading(Heading { level: 1, words, id: "hello".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(md_to_html(&md, &ctx), "<h1 id=\"hello\">Hello</h1>".to_string());
+
+        let words = words!(normal_word!("Hello"));
+        let md = Md::Heading(Heading { level: 2, words, id: "a\"b".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(md_to_html(&md, &ctx), "<h2 id=\"a&quot;b\">Hello</h2>".to_string());
 
         let words = words!(normal_word!("Hello"));
         let md = Md::Sentence(words);
-        assert_eq!(md_to_html(&md), "Hello<br />".to_string());
+        assert_eq!(md_to_html(&md, &ctx), "Hello<br />".to_string());
 
         let words = words!(normal_word!("item"));
-        let items = items!();
-        let item = Item(words, items);
+        let item = Item::new(vec!(Md::Sentence(words)), None);
         let items = items!(item);
-        let md = Md::List(items);
-        assert_eq!(md_to_html(&md), "<ul>\n<li>item</li>\n</ul>\n".to_string());
+        let md = Md::List(List { items, loose: false, span: Span::default() });
+        assert_eq!(md_to_html(&md, &ctx), "<ul>\n<li>item</li>\n</ul>\n".to_string());
     }
 
     #[test]
     fn test_word() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let word = normal_word!("Hello");
-        assert_eq!(word_to_html(&word), "Hello".to_string());
+        assert_eq!(word_to_html(&word, &ctx), "Hello".to_string());
 
         let word = normal_word!("Hello");
         let italic = Word::Italic(words!(word));
-        assert_eq!(word_to_html(&italic), "<i>Hello</i>".to_string());
+        assert_eq!(word_to_html(&italic, &ctx), "<i>Hello</i>".to_string());
 
         let word = normal_word!("Hello");
         let bold = Word::Bold(words!(word));
-        assert_eq!(word_to_html(&bold), "<b>Hello</b>".to_string());
+        assert_eq!(word_to_html(&bold, &ctx), "<b>Hello</b>".to_string());
 
         let word = normal_word!("Hello");
         let strike = Word::StrikeThough(words!(word));
-        assert_eq!(word_to_html(&strike), "<s>Hello</s>".to_string());
+        assert_eq!(word_to_html(&strike, &ctx), "<s>Hello</s>".to_string());
 
         let word = normal_word!("Hello");
         let line = Word::Underline(words!(word));
-        assert_eq!(word_to_html(&line), "<u>Hello</u>".to_string());
+        assert_eq!(word_to_html(&line, &ctx), "<u>Hello</u>".to_string());
+
+        let word = normal_word!("Hello");
+        let mark = Word::Highlight(words!(word));
+        assert_eq!(word_to_html(&mark, &ctx), "<mark>Hello</mark>".to_string());
+
+        let word = normal_word!("2");
+        let sup = Word::Superscript(words!(word));
+        assert_eq!(word_to_html(&sup, &ctx), "<sup>2</sup>".to_string());
+
+        let word = normal_word!("i");
+        let sub = Word::Subscript(words!(word));
+        assert_eq!(word_to_html(&sub, &ctx), "<sub>i</sub>".to_string());
+
+        let code = Word::Code("a < b".to_string());
+        assert_eq!(word_to_html(&code, &ctx), "<code>a &lt; b</code>".to_string());
+
+        let emoji = Word::Emoji { shortcode: "rocket".to_string(), char: "🚀".to_string() };
+        assert_eq!(word_to_html(&emoji, &ctx), "🚀".to_string());
     }
 
     #[test]
     fn test_words_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let word = normal_word!("Hello");
         let word1 = normal_word!("World!");
         let bold = Word::Bold(words!(word1));
         let words = words!(word, bold);
-        assert_eq!(words_to_html(&words), "Hello<b>World!</b>".to_string());
+        assert_eq!(words_to_html(&words, &ctx), "Hello<b>World!</b>".to_string());
     }
 
     #[test]
     fn test_header_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let hello = words!(normal_word!("hello"));
         let world = words!(normal_word!("world"));
-        let header = Record(vec!(hello, world));
-        assert_eq!(header_to_html(&header), "<th>hello</th><th>world</th>".to_string());
+        let header = Record::new(vec!(hello, world));
+        assert_eq!(header_to_html(&header, &ctx), "<th>hello</th><th>world</th>".to_string());
     }
 
     #[test]
     fn test_record_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let hello = words!(normal_word!("hello"));
         let world = words!(normal_word!("world"));
-        let record = Record(vec!(hello, world));
+        let record = Record::new(vec!(hello, world));
         let align = vec!(Align::Left, Align::Left);
-        assert_eq!(record_to_html(&record, &align), "<td align=\"left\">hello</td><td align=\"left\">world</td>".to_string());
+        assert_eq!(record_to_html(&record, &align, &ctx), "<td align=\"left\">hello</td><td align=\"left\">world</td>".to_string());
 
         let hello = words!(normal_word!("hello"));
         let world = words!(normal_word!("world"));
-        let record = Record(vec!(hello, world));
+        let record = Record::new(vec!(hello, world));
         let align = vec!(Align::Center, Align::Right);
-        assert_eq!(record_to_html(&record, &align), "<td align=\"center\">hello</td><td align=\"right\">world</td>".to_string());
+        assert_eq!(record_to_html(&record, &align, &ctx), "<td align=\"center\">hello</td><td align=\"right\">world</td>".to_string());
     }
 
     #[test]
     fn test_records_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let hello = words!(normal_word!("hello"));
-        let record0 = Record(vec!(hello));
+        let record0 = Record::new(vec!(hello));
         let world = words!(normal_word!("world"));
-        let record1 = Record(vec!(world));
+        let record1 = Record::new(vec!(world));
         let records = vec!(record0, record1);
         let aligns = vec!(Align::Left);
-        assert_eq!(records_to_html(&records, &aligns), "<tr><td align=\"left\">hello</td></tr>\n<tr><td align=\"left\">world</td></tr>\n".to_string());
+        assert_eq!(records_to_html(&records, &aligns, &ctx), "<tr><td align=\"left\">hello</td></tr>\n<tr><td align=\"left\">world</td></tr>\n".to_string());
     }
 
     #[test]
     fn test_table_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
         let hello = words!(normal_word!("hello"));
-        let header = Record(vec!(hello));
+        let header = Record::new(vec!(hello));
         let world = words!(normal_word!("world"));
-        let record = Record(vec!(world));
+        let record = Record::new(vec!(world));
         let records = vec!(record);
         let aligns = vec!(Align::Left);
-        let table = Box::new(Table{header, align: aligns, records});
-        assert_eq!(table_to_html(&table), "<table>\n<tr><th>hello</th></tr>\n<tr><td align=\"left\">world</td></tr>\n</table>\n".to_string());
+        let table = Box::new(Table{header, align: aligns, records, span: Span::default() });
+        assert_eq!(table_to_html(&table, &ctx), "<table>\n<tr><th>hello</th></tr>\n<tr><td align=\"left\">world</td></tr>\n</table>\n".to_string());
+    }
+
+    fn leaf(text: &str) -> Item {
+        Item::new(vec!(Md::Sentence(words!(normal_word!(text)))), None)
     }
 
     #[test]
     fn test_item_to_html() {
-        let words = words!(normal_word!("item"));
-        let items = items!();
-        let item = Item(words, items);
-        assert_eq!(item_to_html(&item), "<li>item</li>".to_string());
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        assert_eq!(item_to_html(&leaf("item"), false, &ctx), "<li>item</li>".to_string());
+        assert_eq!(item_to_html(&leaf("item"), true, &ctx), "<li><p>item</p></li>".to_string());
 
         let words = words!(normal_word!("parent"));
-        let words0 = words!(normal_word!("item"));
-        let item0 = Item(words0, items!());
-        let words1 = words!(normal_word!("item1"));
-        let item1 = Item(words1, items!());
-        let items = items!(item0, item1);
-        let item = Item(words, items);
+        let list = List { items: items!(leaf("item"), leaf("item1")), loose: false, span: Span::default() };
+        let item = Item::new(vec!(Md::Sentence(words), Md::List(list)), None);
         let expect = "<li>parent\n<ul>\n<li>item</li>\n<li>item1</li>\n</ul>\n</li>".to_string();
-        assert_eq!(item_to_html(&item), expect);
+        assert_eq!(item_to_html(&item, false, &ctx), expect);
+
+        let task = Item::new(vec!(Md::Sentence(words!(normal_word!("done")))), Some(true));
+        assert_eq!(item_to_html(&task, false, &ctx), "<li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> done</li>".to_string());
     }
 
     #[test]
-    fn test_items_to_html() {
-        let words0 = words!(normal_word!("item"));
-        let item0 = Item(words0, items!());
-        let words1 = words!(normal_word!("item1"));
-        let item1 = Item(words1, items!());
-        let items = items!(item0, item1);
-        assert_eq!(items_to_html(&items), "<ul>\n<li>item</li>\n<li>item1</li>\n</ul>\n".to_string());
+    fn test_list_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let list = List { items: items!(leaf("item"), leaf("item1")), loose: false, span: Span::default() };
+        assert_eq!(list_to_html(&list, &ctx), "<ul>\n<li>item</li>\n<li>item1</li>\n</ul>\n".to_string());
+
+        let list = List { items: items!(leaf("item"), leaf("item1")), loose: true, span: Span::default() };
+        assert_eq!(list_to_html(&list, &ctx), "<ul>\n<li><p>item</p></li>\n<li><p>item1</p></li>\n</ul>\n".to_string());
 
         let words = words!(normal_word!("parent"));
-        let words0 = words!(normal_word!("item"));
-        let item0 = Item(words0, items!());
-        let words1 = words!(normal_word!("item1"));
-        let item1 = Item(words1, items!());
-        let items = items!(item0, item1);
-        let item0 = Item(words, items);
-    
+        let children = List { items: items!(leaf("item"), leaf("item1")), loose: false, span: Span::default() };
+        let item0 = Item::new(vec!(Md::Sentence(words), Md::List(children)), None);
         let words = words!(normal_word!("parent"));
-        let words0 = words!(normal_word!("item"));
-        let item2 = Item(words0, items!());
-        let words1 = words!(normal_word!("item1"));
-        let item3 = Item(words1, items!());
-        let items = items!(item2, item3);
-        let item1 = Item(words, items);
-        let items = items!(item0, item1);
+        let children = List { items: items!(leaf("item"), leaf("item1")), loose: false, span: Span::default() };
+        let item1 = Item::new(vec!(Md::Sentence(words), Md::List(children)), None);
+        let list = List { items: items!(item0, item1), loose: false, span: Span::default() };
         let expect = "<ul>\n<li>parent\n<ul>\n<li>item</li>\n<li>item1</li>\n</ul>\n</li>\n<li>parent\n<ul>\n<li>item</li>\n<li>item1</li>\n</ul>\n</li>\n</ul>\n".to_string();
-        assert_eq!(items_to_html(&items), expect);
+        assert_eq!(list_to_html(&list, &ctx), expect);
+
+        let words = words!(normal_word!("parent"));
+        let header = Record::new(vec!(words!(normal_word!("A"))));
+        let records = vec!(Record::new(vec!(words!(normal_word!("a")))));
+        let table = Md::Table(Box::new(Table{header, align: vec!(Align::Left), records, span: Span::default()}));
+        let list = List { items: items!(Item::new(vec!(Md::Sentence(words), table), None)), loose: true, span: Span::default() };
+        let expect = "<ul>\n<li><p>parent</p>\n<table>\n<tr><th>A</th></tr>\n<tr><td align=\"left\">a</td></tr>\n</table>\n</li>\n</ul>\n".to_string();
+        assert_eq!(list_to_html(&list, &ctx), expect);
+    }
+    #[test]
+    fn test_toc_to_html() {
+        let child = TocEntry { level: 2, id: "b".to_string(), text: "B".to_string(), children: vec!() };
+        let entry = TocEntry { level: 1, id: "a".to_string(), text: "A".to_string(), children: vec!(child) };
+        let expect = "<nav class=\"toc\">\n<ul>\n<li><a href=\"#a\">A</a>\n<ul>\n<li><a href=\"#b\">B</a></li>\n</ul>\n</li>\n</ul>\n</nav>\n";
+        assert_eq!(toc_to_html(&[entry]), expect.to_string());
+        assert_eq!(toc_to_html(&[]), "<nav class=\"toc\"></nav>\n".to_string());
+    }
+
+    #[test]
+    fn test_toc_to_markdown() {
+        let child = TocEntry { level: 2, id: "b".to_string(), text: "B".to_string(), children: vec!() };
+        let entry = TocEntry { level: 1, id: "a".to_string(), text: "A".to_string(), children: vec!(child) };
+        assert_eq!(toc_to_markdown(&[entry]), "- [A](#a)\n  - [B](#b)".to_string());
+
+        let entry = TocEntry { level: 1, id: "a".to_string(), text: "[a](b) \\".to_string(), children: vec!() };
+        assert_eq!(toc_to_markdown(&[entry]), "- [\\[a\\](b) \\\\](#a)".to_string());
+    }
+
+    #[test]
+    fn test_toc_marker_to_html() {
+        let words = words!(normal_word!("A"));
+        let heading = Md::Heading(Heading { level: 1, words, id: "a".to_string(), attrs: Attributes::default(), span: Span::default() });
+        let words = words!(normal_word!("B"));
+        let sub = Md::Heading(Heading { level: 2, words, id: "b".to_string(), attrs: Attributes::default(), span: Span::default() });
+        let mds = vec!(Md::Toc(Span::default()), heading, sub);
+        let expect = "<nav class=\"toc\">\n<ul>\n<li><a href=\"#a\">A</a></li>\n</ul>\n</nav>\n\n<h1 id=\"a\">A</h1>\n<h2 id=\"b\">B</h2>";
+        let opts = ConvertOptions { toc: TocOptions { min_level: 1, max_level: 1 }, ..ConvertOptions::default() };
+        assert_eq!(mds_to_html_with(&mds, &opts), expect.to_string());
+
+        let words = words!(normal_word!("Use "), Word::Code("<script>&".to_string()));
+        let heading = Md::Heading(Heading { level: 1, words, id: "use-script".to_string(), attrs: Attributes::default(), span: Span::default() });
+        let mds = vec!(Md::Toc(Span::default()), heading);
+        let html = mds_to_html_with(&mds, &opts);
+        assert!(html.starts_with("<nav class=\"toc\">\n<ul>\n<li><a href=\"#use-script\">Use &lt;script&gt;&amp;</a></li>"), "{}", html);
+        assert!(!html.contains("<script>"));
+    }
+    #[test]
+    fn test_quote_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let body = vec!(Md::Sentence(words!(normal_word!("Hello"))));
+        let md = Md::Quote(body, Span::default());
+        assert_eq!(md_to_html(&md, &ctx), "<blockquote>\nHello<br />\n</blockquote>\n".to_string());
+    }
+
+    #[test]
+    fn test_admonition_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let body = vec!(Md::Sentence(words!(normal_word!("Careful"))));
+        let md = Md::Admonition { kind: AdmonitionKind::Warning, title: None, body, span: Span::default() };
+        let expect = "<div class=\"admonition warning\">\n<p class=\"admonition-title\">Warning</p>\nCareful<br />\n</div>\n";
+        assert_eq!(md_to_html(&md, &ctx), expect.to_string());
+
+        let title = Some(words!(Word::Bold(words!(normal_word!("Heads up")))));
+        let body = vec!(Md::Sentence(words!(normal_word!("Read"))));
+        let md = Md::Admonition { kind: AdmonitionKind::Tip, title, body, span: Span::default() };
+        let expect = "<div class=\"admonition tip\">\n<p class=\"admonition-title\"><b>Heads up</b></p>\nRead<br />\n</div>\n";
+        assert_eq!(md_to_html(&md, &ctx), expect.to_string());
+    }
+    struct Pages;
+
+    impl WikiResolver for Pages {
+        fn resolve(&self, page: &str) -> Option<String> {
+            (page == "Home").then(|| "/wiki/home".to_string())
+        }
+    }
+
+    #[test]
+    fn test_wiki_link_to_html() {
+        let home = Word::WikiLink { page: "Home".to_string(), label: None };
+        let missing = Word::WikiLink { page: "Missing".to_string(), label: Some("gone".to_string()) };
+
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        assert_eq!(word_to_html(&home, &ctx), "<a href=\"Home\">Home</a>".to_string());
+
+        let opts = ConvertOptions { wiki: Some(Box::new(Pages)), ..ConvertOptions::default() };
+        let ctx = Context::new(&opts);
+        assert_eq!(word_to_html(&home, &ctx), "<a href=\"/wiki/home\">Home</a>".to_string());
+        assert_eq!(word_to_html(&missing, &ctx), "<a class=\"broken\" title=\"Missing\">gone</a>".to_string());
+    }
+    #[test]
+    fn test_attributes_to_html() {
+        let attrs = Attributes {
+            id: Some("main".to_string()),
+            classes: vec!("a".to_string(), "b".to_string()),
+            pairs: vec!(("title".to_string(), "say \"hi\" <now>".to_string())),
+        };
+        let expect = " id=\"main\" class=\"a b\" title=\"say &quot;hi&quot; &lt;now&gt;\"";
+        assert_eq!(attributes_to_html(&attrs), expect.to_string());
+        assert_eq!(attributes_to_html(&Attributes::default()), "".to_string());
+    }
+
+    #[test]
+    fn test_link_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let attrs = Attributes { id: None, classes: vec!("ext".to_string()), pairs: vec!() };
+        let words = words!(normal_word!("Rust"));
+        let link = Word::Link { words, url: "https://a.b/?x=1&y=2".to_string(), title: Some("T".to_string()), attrs };
+        assert_eq!(word_to_html(&link, &ctx), "<a href=\"https://a.b/?x=1&amp;y=2\" title=\"T\" class=\"ext\">Rust</a>".to_string());
+
+        let image = Word::Image { alt: "logo".to_string(), url: "l.png".to_string(), title: None, attrs: Attributes::default() };
+        assert_eq!(word_to_html(&image, &ctx), "<img src=\"l.png\" alt=\"logo\" />".to_string());
+
+        let attrs = Attributes { id: Some("s".to_string()), classes: vec!(), pairs: vec!() };
+        let span = Word::Span { words: words!(normal_word!("text")), attrs };
+        assert_eq!(word_to_html(&span, &ctx), "<span id=\"s\">text</span>".to_string());
+    }
+
+    struct Spoiler;
+
+    impl DirectiveRenderer for Spoiler {
+        fn render(&self, attrs: &Attributes, body: &str) -> String {
+            let summary = attrs.pairs.iter().find(|(key, _)| key == "summary").map_or("Spoiler", |(_, value)| value);
+            format!("<details><summary>{}</summary>{}</details>\n", summary, body)
+        }
+    }
+
+    #[test]
+    fn test_footnotes_to_html() {
+        let reference = |label: &str| Word::Footnote { label: label.to_string() };
+        let sentence = Md::Sentence(words!(normal_word!("A"), reference("b"), reference("a"), reference("x")));
+        let definition = |label: &str, text: &str| Md::Footnote {
+            label: label.to_string(),
+            body: vec!(Md::Sentence(words!(normal_word!(text)))),
+            span: Span::default(),
+        };
+        let mds = vec!(sentence, definition("a", "Alpha"), definition("b", "Beta"), definition("c", "Unused"));
+        let expect = concat!(
+            "A<sup class=\"footnote-ref\"><a href=\"#fn-b\" id=\"fnref-b\">1</a></sup>",
+            "<sup class=\"footnote-ref\"><a href=\"#fn-a\" id=\"fnref-a\">2</a></sup>[^x]<br />\n",
+            "<section class=\"footnotes\">\n<ol>\n",
+            "<li id=\"fn-b\">Beta <a href=\"#fnref-b\" class=\"footnote-backref\">↩</a></li>\n",
+            "<li id=\"fn-a\">Alpha <a href=\"#fnref-a\" class=\"footnote-backref\">↩</a></li>\n",
+            "</ol>\n</section>\n",
+        );
+        assert_eq!(mds_to_html(&mds), expect.to_string());
+    }
 
+    struct Ins;
+
+    impl InlineRenderer for Ins {
+        fn render(&self, body: &str) -> String {
+            format!("<ins>{}</ins>", body)
+        }
+    }
+
+    #[test]
+    fn test_extension_to_html() {
+        let spoiler = Word::Extension { name: "spoiler".to_string(), words: words!(normal_word!("a & b")) };
+        let ins = Word::Extension { name: "ins".to_string(), words: words!(Word::Bold(words!(normal_word!("new")))) };
+
+        let mut opts = ConvertOptions::default();
+        opts.inlines.insert("ins".to_string(), Box::new(Ins));
+        let ctx = Context::new(&opts);
+        assert_eq!(word_to_html(&spoiler, &ctx), "<span class=\"spoiler\">a & b</span>".to_string());
+        assert_eq!(word_to_html(&ins, &ctx), "<ins><b>new</b></ins>".to_string());
+    }
+
+    #[test]
+    fn test_container_to_html() {
+        let body = vec!(Md::Sentence(words!(normal_word!("Hidden"))));
+        let attrs = Attributes { id: Some("s".to_string()), classes: vec!("wide".to_string()), pairs: vec!() };
+        let note = Md::Container { name: "note".to_string(), attrs, body, span: Span::default() };
+        let body = vec!(Md::Sentence(words!(normal_word!("Hidden"))));
+        let attrs = Attributes { id: None, classes: vec!(), pairs: vec!(("summary".to_string(), "Ending".to_string())) };
+        let spoiler = Md::Container { name: "spoiler".to_string(), attrs, body, span: Span::default() };
+
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        assert_eq!(md_to_html(&note, &ctx), "<div id=\"s\" class=\"note wide\">\nHidden<br />\n</div>\n".to_string());
+        assert_eq!(md_to_html(&spoiler, &ctx), "<div class=\"spoiler\" summary=\"Ending\">\nHidden<br />\n</div>\n".to_string());
+
+        let mut opts = ConvertOptions::default();
+        opts.directives.insert("spoiler".to_string(), Box::new(Spoiler));
+        let ctx = Context::new(&opts);
+        assert_eq!(md_to_html(&spoiler, &ctx), "<details><summary>Ending</summary>Hidden<br /></details>\n".to_string());
+    }
+
+    #[test]
+    fn test_details_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let body = vec!(Md::Sentence(words!(normal_word!("Restart it."))));
+        let details = Md::Details { summary: words!(Word::Bold(words!(normal_word!("Hangs")))), open: true, body, span: Span::default() };
+        assert_eq!(md_to_html(&details, &ctx), "<details open>\n<summary><b>Hangs</b></summary>\nRestart it.<br />\n</details>\n".to_string());
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_citations_to_html() {
+        let cite = |key: &str| Cite { key: key.to_string(), locator: None, suppress_author: false };
+        let known = Word::Citation { cites: vec!(cite("doe")), in_text: false };
+        let unknown = Word::Citation { cites: vec!(cite("nope")), in_text: true };
+        let mds = vec!(Md::Sentence(words!(known, unknown)));
+        let fields = vec!(("author".to_string(), "Doe, Jane".to_string()), ("year".to_string(), "2020".to_string()));
+        let entry = Entry { kind: "book".to_string(), key: "doe".to_string(), fields };
+        let opts = ConvertOptions { bibliography: vec!(entry), citation_style: CitationStyle::Chicago, ..ConvertOptions::default() };
+        let html = "<span class=\"citation\" data-cites=\"doe\">(Doe 2020)</span><span class=\"citation unknown\" data-cites=\"nope\">nope?</span><br />\n\
+                    <div id=\"refs\" class=\"references\">\n<p id=\"ref-doe\">Doe, Jane. 2020. <i>doe</i>.</p>\n</div>\n";
+        assert_eq!(mds_to_html_with(&mds, &opts), html.to_string());
+    }
+
+    #[test]
+    fn test_abbreviation_to_html() {
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        let abbr = Word::Abbreviation { abbr: "R&D".to_string(), title: "Research & \"Development\"".to_string() };
+        assert_eq!(word_to_html(&abbr, &ctx), "<abbr title=\"Research &amp; &quot;Development&quot;\">R&amp;D</abbr>".to_string());
+    
+        let definition = Md::Abbreviation { abbr: "R&D".to_string(), title: "Research".to_string(), span: Span::default() };
+        let mds = vec!(Md::Sentence(words!(normal_word!("A"))), definition, Md::Sentence(words!(normal_word!("B"))));
+        assert_eq!(mds_to_html(&mds), "A<br />\nB<br />".to_string());
+    }
+
+    #[test]
+    fn test_code_block_to_html() {
+        let attrs = Attributes { id: Some("main".to_string()), classes: vec!(), pairs: vec!() };
+        let block = CodeBlock { lang: "rust".to_string(), code: "a < b\n".to_string(), attrs, span: Span::default() };
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        assert_eq!(code_block_to_html(&block, &ctx), "<pre id=\"main\"><code class=\"language-rust\">a &lt; b\n</code></pre>\n".to_string());
+    }
+
+    struct Svg;
+
+    impl CodeTransformer for Svg {
+        fn transform(&self, block: &CodeBlock) -> Option<Transformed> {
+            (block.code != "skip\n").then(|| Transformed::Html(format!("<svg>{}</svg>\n", block.code.trim())))
+        }
+    }
+
+    struct Quoted;
+
+    impl CodeTransformer for Quoted {
+        fn transform(&self, block: &CodeBlock) -> Option<Transformed> {
+            let words = words!(normal_word!(block.code.trim()));
+            Some(Transformed::Blocks(vec!(Md::Quote(vec!(Md::Sentence(words)), Span::default()))))
+        }
+    }
+
+    #[test]
+    fn test_code_transformers() {
+        let block = |lang: &str, code: &str| CodeBlock { lang: lang.to_string(), code: code.to_string(), attrs: Attributes::default(), span: Span::default() };
+        let opts = ConvertOptions::default();
+        let ctx = Context::new(&opts);
+        assert_eq!(code_block_to_html(&block("mermaid", "a --> b\n"), &ctx), "<pre class=\"mermaid\">a --&gt; b\n</pre>\n".to_string());
+
+        let mut opts = ConvertOptions::default();
+        opts.code.insert("dot".to_string(), Box::new(Svg));
+        opts.code.insert("quote".to_string(), Box::new(Quoted));
+        let ctx = Context::new(&opts);
+        assert_eq!(code_block_to_html(&block("dot", "a -> b\n"), &ctx), "<svg>a -> b</svg>\n".to_string());
+        assert_eq!(code_block_to_html(&block("dot", "skip\n"), &ctx), "<pre class=\"dot\">skip\n</pre>\n".to_string());
+        assert_eq!(code_block_to_html(&block("quote", "hi\n"), &ctx), "<blockquote>\nhi<br />\n</blockquote>\n".to_string());
+    }
+}
@@ -1,23 +1,48 @@
-mod parser;
-mod convert;
+use app::{parser, convert};
+use app::parser::variables::{self, VariableOptions};
 
+use std::env;
 use std::fs;
 use std::io;
+use std::path::Path;
 
 fn write_to_file(path: &str, content: &str) -> io::Result<()> {
     fs::write(path, content)?;
     Ok(())
 }
 
-fn read_file_to_string(path: &str) -> io::Result<String> {
-    let contents = fs::read_to_string(path)?;
-    Ok(contents)
+fn invalid(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+/// `--define key=value` (repeatable), `--config path` and `--strict`.
+fn variable_options(mut args: impl Iterator<Item = String>) -> io::Result<VariableOptions> {
+    let mut vars = VariableOptions::default();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--define" => {
+                let define = args.next().unwrap_or_default();
+                let pair = variables::define(&define).ok_or_else(|| invalid(format!("expected key=value, got `{}`", define)))?;
+                vars.defines.push(pair);
+            },
+            "--config" => {
+                let path = args.next().ok_or_else(|| invalid("--config needs a path".to_string()))?;
+                vars.config = variables::config(&fs::read_to_string(path)?);
+            },
+            "--strict" => vars.strict = true,
+            _ => return Err(invalid(format!("unknown argument `{}`", arg))),
+        }
+    }
+    Ok(vars)
 }
 
 fn main()  -> io::Result<()> {
-    let contents = read_file_to_string("./test.md")?;
-    let contents: &str = &contents;
-    let mds = parser::parser::parse(&contents);
+    let vars = variable_options(env::args().skip(1))?;
+    let opts = parser::parser::ParserOptions::default();
+    let path = Path::new("./test.md");
+    let contents = fs::read_to_string(path)?;
+    let contents = parser::include::expand(&contents, path, &parser::include::Disk, &opts).map_err(io::Error::other)?;
+    let mds = variables::parse_with_variables(&contents, &opts, &vars).map_err(io::Error::other)?;
     let output = convert::convert::mds_to_html(&mds);
     let output: &str = &output;
     write_to_file("./test.html", output)?;
@@ -1,5 +1,30 @@
+#[allow(clippy::module_inception)]
 pub mod parser;
+pub mod options;
+pub mod error;
+pub mod span;
+pub mod block;
+pub mod inline;
 mod heading;
 mod sentence;
 mod table;
-mod list;
\ No newline at end of file
+mod list;
+mod quote;
+mod slug;
+pub mod toc;
+pub mod emoji;
+mod emoji_table;
+pub mod wiki;
+mod attributes;
+mod code;
+mod link;
+pub mod abbreviation;
+pub mod reference;
+mod smart;
+mod container;
+mod details;
+mod footnote;
+pub mod citation;
+pub mod bibtex;
+pub mod include;
+pub mod variables;
@@ -0,0 +1,106 @@
+use crate::parser::parser::*;
+
+pub fn abbreviation<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.abbreviations { return None }
+    let (line, rest) = split_first_pattern(texts, "\n");
+    let text = consume(line.trim_start(), "*[")?;
+    let (abbr, title) = text.split_once("]:")?;
+    let abbr = abbr.trim();
+    if abbr.is_empty() || abbr.contains(']') { return None }
+    let token = Md::Abbreviation { abbr: abbr.to_string(), title: title.trim().to_string(), span: Span::default() };
+    Some(ParsedResult::new(token, rest))
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn matches<'a>(text: &str, start: usize, abbrs: &'a [(String, String)]) -> Option<&'a (String, String)> {
+    if text[..start].chars().next_back().is_some_and(is_word_char) { return None }
+    abbrs.iter().find(|(abbr, _)| {
+        let rest = &text[start..];
+        rest.starts_with(abbr.as_str()) && !rest[abbr.len()..].chars().next().is_some_and(is_word_char)
+    })
+}
+
+fn split(text: &str, abbrs: &[(String, String)]) -> Option<Vec<(usize, Word)>> {
+    let mut tokens: Vec<(usize, Word)> = vec!();
+    let mut plain = 0;
+    let mut index = 0;
+    while index < text.len() {
+        if let Some((abbr, title)) = matches(text, index, abbrs) {
+            if plain < index {
+                tokens.push((plain, Word::Normal(text[plain..index].to_string())));
+            }
+            tokens.push((index, Word::Abbreviation { abbr: abbr.clone(), title: title.clone() }));
+            index += abbr.len();
+            plain = index;
+        } else {
+            index += text[index..].chars().next().map_or(1, char::len_utf8);
+        }
+    }
+    if tokens.is_empty() { return None }
+    if plain < text.len() {
+        tokens.push((plain, Word::Normal(text[plain..].to_string())));
+    }
+    Some(tokens)
+}
+
+/// Removes `*[ABBR]: title` definitions and wraps whole-word uses of them.
+pub fn abbreviations(mds: &mut Vec<Md>) {
+    let mut abbrs: Vec<(String, String)> = vec!();
+    walk_blocks_mut(mds, &mut |blocks| blocks.retain(|md| match md {
+        Md::Abbreviation { abbr, title, .. } => {
+            abbrs.push((abbr.clone(), title.clone()));
+            false
+        },
+        _ => true,
+    }));
+    if abbrs.is_empty() { return }
+    abbrs.sort_by_key(|(abbr, _)| std::cmp::Reverse(abbr.len()));
+    walk_words_mut(mds, &mut |words| split_normal(words, &mut |text| split(text, &abbrs)));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn abbr(abbr: &str, title: &str) -> Word {
+        Word::Abbreviation { abbr: abbr.to_string(), title: title.to_string() }
+    }
+
+    #[test]
+    fn test_abbreviation() {
+        let opts = ParserOptions { abbreviations: true, ..ParserOptions::default() };
+        let token = Md::Abbreviation { abbr: "HTML".to_string(), title: "Hyper Text Markup Language".to_string(), span: Span::default() };
+        let rest = "next";
+        assert_eq!(abbreviation("*[HTML]: Hyper Text Markup Language\nnext", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(abbreviation("*[]: empty", &opts), None);
+        assert_eq!(abbreviation("*[HTML] no colon", &opts), None);
+        assert_eq!(abbreviation("*[HTML]: title", &ParserOptions::default()), None);
+    }
+
+    #[test]
+    fn test_split() {
+        let abbrs = vec!(
+            ("HTML5".to_string(), "five".to_string()),
+            ("HTML".to_string(), "markup".to_string()),
+        );
+        let token = vec!((0, abbr("HTML", "markup")), (4, normal_word!(" and ")), (9, abbr("HTML5", "five")), (14, normal_word!(".")));
+        assert_eq!(split("HTML and HTML5.", &abbrs), Some(token));
+        assert_eq!(split("XHTML HTMLs", &abbrs), None);
+    }
+
+    #[test]
+    fn test_abbreviations() {
+        let definition = Md::Abbreviation { abbr: "W3C".to_string(), title: "World Wide Web Consortium".to_string(), span: Span::default() };
+        let code = Word::Code("W3C".to_string());
+        let mut mds = vec!(Md::Sentence(words!(normal_word!("The W3C "), code)), definition);
+        abbreviations(&mut mds);
+
+        let code = Word::Code("W3C".to_string());
+        let words = words!(normal_word!("The "), abbr("W3C", "World Wide Web Consortium"), normal_word!(" "), code);
+        assert_eq!(mds, vec!(Md::Sentence(words)));
+    }
+}
@@ -0,0 +1,101 @@
+use crate::parser::parser::*;
+
+fn is_name(c: char) -> bool {
+    !c.is_whitespace() && !"{}#.=\"'".contains(c)
+}
+
+fn name(text: &str) -> Option<(&str, &str)> {
+    let end = text.find(|c: char| !is_name(c)).unwrap_or(text.len());
+    if end == 0 { return None }
+    Some((&text[..end], &text[end..]))
+}
+
+fn value(text: &str) -> Option<(&str, &str)> {
+    match text.chars().next()? {
+        quote @ ('"' | '\'') => {
+            let body = &text[1..];
+            let end = body.find(quote)?;
+            Some((&body[..end], &body[(end+1)..]))
+        },
+        _ => name(text),
+    }
+}
+
+pub fn attributes(text: &str) -> Option<ParsedResult<'_, Attributes>> {
+    let mut text = consume(text, "{")?;
+    let mut attrs = Attributes::default();
+    loop {
+        text = text.trim_start();
+        if let Some(rest) = consume(text, "}") {
+            if attrs == Attributes::default() { return None }
+            return Some(ParsedResult::new(attrs, rest))
+        }
+        if let Some(rest) = consume(text, "#") {
+            let (id, rest) = name(rest)?;
+            attrs.id = Some(id.to_string());
+            text = rest;
+        } else if let Some(rest) = consume(text, ".") {
+            let (class, rest) = name(rest)?;
+            attrs.classes.push(class.to_string());
+            text = rest;
+        } else {
+            let (key, rest) = name(text)?;
+            let (value, rest) = value(consume(rest, "=")?)?;
+            attrs.pairs.push((key.to_string(), value.to_string()));
+            text = rest;
+        }
+    }
+}
+
+/// Splits a trailing `{...}` attribute list off the end of a line.
+pub fn trailing_attributes(text: &str) -> (&str, Attributes) {
+    let trimmed = text.trim_end();
+    if !trimmed.ends_with('}') { return (text, Attributes::default()) }
+    let found = trimmed.rmatch_indices('{').find_map(|(n, _)| {
+        let attrs = attributes(&trimmed[n..])?;
+        attrs.rest.is_empty().then_some((n, attrs.token))
+    });
+    match found {
+        Some((n, attrs)) => (trimmed[..n].trim_end(), attrs),
+        None => (text, Attributes::default()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attrs(id: Option<&str>, classes: &[&str], pairs: &[(&str, &str)]) -> Attributes {
+        Attributes {
+            id: id.map(|id| id.to_string()),
+            classes: classes.iter().map(|class| class.to_string()).collect(),
+            pairs: pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect(),
+        }
+    }
+
+    #[test]
+    fn test_attributes() {
+        let token = attrs(Some("main"), &["a", "b"], &[("k", "v"), ("title", "x y")]);
+        let rest = " after";
+        assert_eq!(attributes("{#main .a .b k=v title=\"x y\"} after"), Some(ParsedResult{token, rest}));
+
+        let token = attrs(None, &["note"], &[("data-x", "1}")]);
+        let rest = "";
+        assert_eq!(attributes("{ .note data-x='1}' }"), Some(ParsedResult{token, rest}));
+
+        assert_eq!(attributes("{}"), None);
+        assert_eq!(attributes("{#}"), None);
+        assert_eq!(attributes("{#a b}"), None);
+        assert_eq!(attributes("{k=\"open}"), None);
+        assert_eq!(attributes("{.a"), None);
+    }
+
+    #[test]
+    fn test_trailing_attributes() {
+        assert_eq!(trailing_attributes("Hello {#custom-id}"), ("Hello", attrs(Some("custom-id"), &[], &[])));
+        assert_eq!(trailing_attributes("Hello{.a k=v}  "), ("Hello", attrs(None, &["a"], &[("k", "v")])));
+        assert_eq!(trailing_attributes("Hello {#}"), ("Hello {#}", Attributes::default()));
+        assert_eq!(trailing_attributes("Hello {#a b}"), ("Hello {#a b}", Attributes::default()));
+        assert_eq!(trailing_attributes("Hello"), ("Hello", Attributes::default()));
+    }
+}
@@ -0,0 +1,150 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Entry {
+    /// Lowercased entry type, such as `article` or `book`.
+    pub kind: String,
+    pub key: String,
+    /// Field names are lowercased; values have braces and escapes removed.
+    pub fields: Vec<(String, String)>,
+}
+
+impl Entry {
+    pub fn field(&self, name: &str) -> Option<&str> {
+        self.fields.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+}
+
+/// Index of the bracket closing the one `text` starts with.
+fn closing(text: &str) -> Option<usize> {
+    let close = if text.starts_with('(') { ')' } else { '}' };
+    let mut depth = 0;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            '{' => depth += 1,
+            '}' if depth > 0 => depth -= 1,
+            c if c == close && depth == 0 => return Some(i),
+            _ => {},
+        }
+    }
+    None
+}
+
+fn split_fields(body: &str) -> Vec<&str> {
+    let mut fields: Vec<&str> = vec!();
+    let (mut depth, mut quoted, mut start) = (0, false, 0);
+    for (i, c) in body.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            '"' if depth == 0 => quoted = !quoted,
+            ',' if depth == 0 && !quoted => {
+                fields.push(&body[start..i]);
+                start = i + 1;
+            },
+            _ => {},
+        }
+    }
+    fields.push(&body[start..]);
+    fields
+}
+
+fn clean(value: &str) -> String {
+    let mut text = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '{' | '}' => {},
+            '\\' if chars.peek().is_some_and(|c| "&%$_#{}".contains(*c)) => text.extend(chars.next()),
+            c => text.push(c),
+        }
+    }
+    text.split_whitespace().collect::<Vec<&str>>().join(" ")
+}
+
+fn value(text: &str) -> String {
+    let text = text.trim();
+    let inner = text.strip_prefix('"').and_then(|t| t.strip_suffix('"'));
+    clean(inner.unwrap_or(text))
+}
+
+fn entry(kind: &str, body: &str) -> Option<Entry> {
+    let (key, fields) = body.split_once(',').unwrap_or((body, ""));
+    let key = key.trim();
+    if key.is_empty() { return None }
+    let fields = split_fields(fields).into_iter()
+        .filter_map(|field| field.split_once('='))
+        .map(|(name, text)| (name.trim().to_lowercase(), value(text)))
+        .collect();
+    Some(Entry { kind: kind.to_string(), key: key.to_string(), fields })
+}
+
+/// Parses the entries of a BibTeX database, skipping anything malformed.
+pub fn bibtex(text: &str) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = vec!();
+    let mut rest = text;
+    while let Some(start) = rest.find('@') {
+        rest = &rest[(start+1)..];
+        let len = rest.find(|c: char| !c.is_ascii_alphabetic()).unwrap_or(rest.len());
+        let kind = rest[..len].to_lowercase();
+        let open = len + (rest[len..].len() - rest[len..].trim_start().len());
+        if kind.is_empty() || !rest[open..].starts_with(['{', '(']) { continue }
+        let Some(close) = closing(&rest[open..]).map(|n| open + n) else { break };
+        let body = &rest[(open+1)..close];
+        rest = &rest[(close+1)..];
+        if ["comment", "string", "preamble"].contains(&kind.as_str()) { continue }
+        entries.extend(entry(&kind, body));
+    }
+    entries
+}
+
+pub fn read(path: impl AsRef<Path>) -> io::Result<Vec<Entry>> {
+    Ok(bibtex(&fs::read_to_string(path)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fields(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_split_fields() {
+        assert_eq!(split_fields(" a = {x, y}, b = \"p, q\", c = 3"), vec!(" a = {x, y}", " b = \"p, q\"", " c = 3"));
+    }
+
+    #[test]
+    fn test_clean() {
+        assert_eq!(clean("The {R}ust   \\& {{C}}"), "The Rust & C");
+    }
+
+    #[test]
+    fn test_bibtex() {
+        let text = "
+@comment{ignored}
+@Article{knuth1984,
+  Author = {Knuth, Donald E.},
+  title  = \"Literate {Programming}\",
+  year   = 1984,
+}
+not an entry @ here
+@book(lamport94, author = {Leslie Lamport}, title = {LaTeX})
+";
+        let knuth = Entry {
+            kind: "article".to_string(),
+            key: "knuth1984".to_string(),
+            fields: fields(&[("author", "Knuth, Donald E."), ("title", "Literate Programming"), ("year", "1984")]),
+        };
+        let lamport = Entry {
+            kind: "book".to_string(),
+            key: "lamport94".to_string(),
+            fields: fields(&[("author", "Leslie Lamport"), ("title", "LaTeX")]),
+        };
+        assert_eq!(bibtex(text), vec!(knuth, lamport));
+        assert_eq!(bibtex("@book{open, title = {x}"), vec!());
+    }
+}
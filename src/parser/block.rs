@@ -0,0 +1,227 @@
+use crate::parser::parser::*;
+use super::heading::heading;
+use super::sentence::sentence;
+use super::table::table;
+use super::list::list;
+use super::toc::toc_marker;
+use super::quote::quote;
+use super::code::code_block;
+use super::abbreviation::abbreviation;
+use super::container::container;
+use super::details::details;
+use super::footnote::footnote;
+use std::sync::Arc;
+
+/// A block syntax. `parse` is given the rest of the document and returns the
+/// block at its start, usually an `Md::Container` rendered through
+/// `ConvertOptions::directives`, with any spans inside it relative to
+/// `text`; nested content can be parsed with `parse_blocks` or
+/// `parse_lines`, which use the same parsers as the document.
+pub trait BlockParser: Send + Sync {
+    /// The name `Parser::disable` refers to.
+    fn name(&self) -> &str;
+    /// Parsers are tried from the lowest priority up. The built-in ones use
+    /// multiples of 10, ending with `sentence` at 100, which accepts any line.
+    fn priority(&self) -> u32;
+    fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>>;
+}
+
+type BlockFn = for<'a> fn(&'a str, &ParserOptions) -> Option<ParsedResult<'a, Md>>;
+
+struct Builtin {
+    name: &'static str,
+    priority: u32,
+    parse: BlockFn,
+}
+
+impl BlockParser for Builtin {
+    fn name(&self) -> &str {
+        self.name
+    }
+
+    fn priority(&self) -> u32 {
+        self.priority
+    }
+
+    fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        (self.parse)(text, opts)
+    }
+}
+
+const BUILTINS: [Builtin; 11] = [
+    Builtin { name: "code", priority: 10, parse: code_block },
+    Builtin { name: "container", priority: 20, parse: container },
+    Builtin { name: "details", priority: 30, parse: details },
+    Builtin { name: "abbreviation", priority: 40, parse: abbreviation },
+    Builtin { name: "footnote", priority: 45, parse: footnote },
+    Builtin { name: "table", priority: 50, parse: table },
+    Builtin { name: "list", priority: 60, parse: list },
+    Builtin { name: "heading", priority: 70, parse: heading },
+    Builtin { name: "quote", priority: 80, parse: quote },
+    Builtin { name: "toc", priority: 90, parse: toc_marker },
+    Builtin { name: "sentence", priority: 100, parse: sentence },
+];
+
+/// The enabled block parsers in the order they are tried. Registered
+/// parsers come after built-in ones of the same priority.
+pub(crate) fn block_parsers(opts: &ParserOptions) -> Vec<&dyn BlockParser> {
+    let builtins = BUILTINS.iter().map(|parser| parser as &dyn BlockParser);
+    let mut parsers: Vec<&dyn BlockParser> = builtins
+        .chain(opts.extensions.blocks.iter().map(|parser| parser.as_ref()))
+        .filter(|parser| !opts.extensions.disabled.iter().any(|name| name == parser.name()))
+        .collect();
+    parsers.sort_by_key(|parser| parser.priority());
+    parsers
+}
+
+/// Parses documents with extra block and inline syntaxes, or without some
+/// built-in block ones.
+///
+/// ```
+/// use app::parser::parser::*;
+///
+/// struct Callout;
+///
+/// impl BlockParser for Callout {
+///     fn name(&self) -> &str { "callout" }
+///     fn priority(&self) -> u32 { 65 }
+///     fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+///         let (line, rest) = split_first_pattern(text, "\n");
+///         let body = parse_lines(text, &[consume(line, "!!! ")?], opts).ok()?;
+///         let token = Md::Container { name: "callout".to_string(), attrs: Attributes::default(), body, span: Span::default() };
+///         Some(ParsedResult::new(token, rest))
+///     }
+/// }
+///
+/// let parser = Parser::new(ParserOptions::default())
+///     .block(Callout)
+///     .inline(Delimited::new("spoiler", "||", "||"))
+///     .disable("table")?;
+/// let mds = parser.parse("!!! Look ||here||\n| not | a table |")?;
+/// assert!(matches!(&mds[0], Md::Container { name, .. } if name == "callout"));
+/// assert!(matches!(mds[1], Md::Sentence(_)));
+/// # Ok::<(), Box<dyn std::error::Error>>(())
+/// ```
+#[derive(Default)]
+pub struct Parser {
+    opts: ParserOptions,
+}
+
+impl Parser {
+    pub fn new(opts: ParserOptions) -> Parser {
+        Parser { opts }
+    }
+
+    pub fn block(mut self, parser: impl BlockParser + 'static) -> Parser {
+        self.opts.extensions.blocks.push(Arc::new(parser));
+        self
+    }
+
+    pub fn inline(mut self, parser: impl InlineParser + 'static) -> Parser {
+        self.opts.extensions.inlines.push(Arc::new(parser));
+        self
+    }
+
+    /// Skips the block parser called `name`: one registered earlier, or one
+    /// of the built-in `code`, `container`, `details`, `abbreviation`,
+    /// `footnote`, `table`, `list`, `heading`, `quote`, `toc` and `sentence`.
+    /// Disabling `sentence` makes text that no other parser accepts an error.
+    /// Fails with `UnknownParser` when no parser is called `name`, so a
+    /// misspelt name is not silently ignored.
+    pub fn disable(mut self, name: &str) -> Result<Parser, UnknownParser> {
+        let builtin = BUILTINS.iter().any(|parser| parser.name == name);
+        let registered = self.opts.extensions.blocks.iter().any(|parser| parser.name() == name);
+        if !builtin && !registered { return Err(UnknownParser(name.to_string())) }
+        self.opts.extensions.disabled.push(name.to_string());
+        Ok(self)
+    }
+
+    pub fn options(&self) -> &ParserOptions {
+        &self.opts
+    }
+
+    pub fn parse(&self, text: &str) -> Result<Vec<Md>, ParseError> {
+        parse_with(text, &self.opts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    /// `%%%` fences around an aside.
+    struct Aside;
+
+    impl BlockParser for Aside {
+        fn name(&self) -> &str {
+            "aside"
+        }
+
+        fn priority(&self) -> u32 {
+            5
+        }
+
+        fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+            let body = consume(text, "%%%\n")?;
+            let end = body.find("\n%%%")?;
+            let lines: Vec<&str> = body[..end].lines().collect();
+            let rest = split_first_pattern(&body[(end+1)..], "\n").1;
+            let body = parse_lines(text, &lines, opts).ok()?;
+            let token = Md::Container { name: "aside".to_string(), attrs: Attributes::default(), body, span: Span::default() };
+            Some(ParsedResult::new(token, rest))
+        }
+    }
+
+    #[test]
+    fn test_order() {
+        let opts = ParserOptions::default();
+        let names: Vec<&str> = block_parsers(&opts).iter().map(|p| p.name()).collect();
+        assert_eq!(names.first(), Some(&"code"));
+        assert_eq!(names.last(), Some(&"sentence"));
+
+        let parser = Parser::default().block(Aside).disable("code").unwrap();
+        let names: Vec<&str> = block_parsers(parser.options()).iter().map(|p| p.name()).collect();
+        assert_eq!(names[..2], ["aside", "container"]);
+    }
+
+    #[test]
+    fn test_custom_block() {
+        let parser = Parser::default().block(Aside);
+        let mds = parser.parse("%%%\n# Inside\n%%%\nafter").unwrap();
+        assert!(matches!(&mds[0], Md::Container { name, body, .. } if name == "aside" && matches!(body[0], Md::Heading(_))));
+        assert_eq!(mds[0].span().range(), 0..16);
+        assert_eq!(mds[0].children()[0][0].span().range(), 4..12);
+        assert_eq!(mds.without_spans()[1], Md::Sentence(words!(normal_word!("after"))));
+    }
+
+    #[test]
+    fn test_shared_options() {
+        fn shareable<T: Send + Sync>(_: &T) {}
+        let parser = Parser::default().block(Aside).inline(Delimited::new("spoiler", "||", "||"));
+        let opts = parser.options().clone();
+        shareable(&opts);
+        assert_eq!(&opts, parser.options());
+        assert_ne!(&opts, Parser::default().block(Aside).options());
+        assert_ne!(opts, ParserOptions::default());
+    }
+
+    #[test]
+    fn test_disable() {
+        let parser = Parser::default().disable("heading").unwrap();
+        assert_eq!(parser.parse("# Title").without_spans(), Ok(vec!(Md::Sentence(words!(normal_word!("# Title"))))));
+
+        let parser = Parser::default().disable("sentence").unwrap();
+        assert_eq!(parser.parse("text").unwrap_err().kind, ParseErrorKind::UnexpectedInput);
+
+        let parser = Parser::default().block(Aside).disable("aside").unwrap();
+        assert_eq!(parser.parse("%%%\n%%%").without_spans().unwrap()[0], Md::Sentence(words!(normal_word!("%%%"))));
+    }
+
+    #[test]
+    fn test_disable_unknown() {
+        let error = Parser::default().disable("tabel").err();
+        assert_eq!(error, Some(UnknownParser("tabel".to_string())));
+        assert_eq!(error.unwrap().to_string(), "no block parser called `tabel`");
+    }
+}
@@ -0,0 +1,126 @@
+use crate::parser::parser::*;
+use super::link::bracket;
+use super::bibtex::Entry;
+
+fn is_key_char(c: char) -> bool {
+    c.is_alphanumeric() || "_:.#$%&-+?<>~/".contains(c)
+}
+
+/// A citation key, without the punctuation that may follow it in a sentence.
+fn key(text: &str) -> Option<(&str, &str)> {
+    if !text.starts_with(|c: char| c.is_alphanumeric() || c == '_') { return None }
+    let end = text.find(|c: char| !is_key_char(c)).unwrap_or(text.len());
+    let key = text[..end].trim_end_matches(|c: char| !c.is_alphanumeric() && c != '_');
+    Some((key, &text[key.len()..]))
+}
+
+fn cite(text: &str) -> Option<Cite> {
+    let text = text.trim();
+    let (suppress_author, text) = match consume(text, "-") {
+        Some(text) => (true, text),
+        None => (false, text),
+    };
+    let (key, rest) = key(consume(text, "@")?)?;
+    let locator = match rest.trim() {
+        "" => None,
+        rest => Some(consume(rest, ",")?.trim().to_string()),
+    };
+    Some(Cite { key: key.to_string(), locator, suppress_author })
+}
+
+pub(crate) fn citation<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.citations { return None }
+    if let Some(rest) = consume(text, "@") {
+        let (key, rest) = key(rest)?;
+        let cite = Cite { key: key.to_string(), locator: None, suppress_author: false };
+        return Some(ParsedResult::new(Word::Citation { cites: vec!(cite), in_text: true }, rest))
+    }
+    let (inner, rest) = bracket(text)?;
+    if rest.starts_with(['(', '[', '{']) { return None }
+    let cites = inner.split(';').map(cite).collect::<Option<Vec<Cite>>>()?;
+    Some(ParsedResult::new(Word::Citation { cites, in_text: false }, rest))
+}
+
+/// Where a textual `@key` citation could start: an `@` after the first
+/// character that does not follow a word character, so emails are skipped.
+pub(crate) fn citation_start(text: &str) -> Option<usize> {
+    text.char_indices().skip(1).find_map(|(i, c)| {
+        let after_word = text[..i].chars().next_back().is_some_and(|p| p.is_alphanumeric() || p == '_');
+        (c == '@' && !after_word).then_some(i)
+    })
+}
+
+/// Cited keys in order of first appearance.
+pub fn keys(mds: &[Md]) -> Vec<String> {
+    let mut keys: Vec<String> = vec!();
+    walk_words(mds, &mut |words| {
+        for word in words.0.iter() {
+            let Word::Citation { cites, .. } = word else { continue };
+            for cite in cites {
+                if !keys.contains(&cite.key) {
+                    keys.push(cite.key.clone());
+                }
+            }
+        }
+    });
+    keys
+}
+
+/// One warning per cited key that `entries` does not define.
+pub fn warnings(mds: &[Md], entries: &[Entry]) -> Vec<String> {
+    keys(mds).into_iter()
+        .filter(|key| !entries.iter().any(|entry| &entry.key == key))
+        .map(|key| format!("unknown citation key: {}", key))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::words;
+
+    fn cite(key: &str, locator: Option<&str>, suppress_author: bool) -> Cite {
+        Cite { key: key.to_string(), locator: locator.map(|l| l.to_string()), suppress_author }
+    }
+
+    #[test]
+    fn test_key() {
+        assert_eq!(key("doe2020."), Some(("doe2020", ".")));
+        assert_eq!(key("a:b/c, p. 4"), Some(("a:b/c", ", p. 4")));
+        assert_eq!(key(".x"), None);
+    }
+
+    #[test]
+    fn test_citation() {
+        let opts = ParserOptions { citations: true, ..ParserOptions::default() };
+        let cites = vec!(cite("doe2020", Some("p. 4"), false), cite("roe", None, true));
+        let token = Word::Citation { cites, in_text: false };
+        let rest = ".";
+        assert_eq!(citation("[@doe2020, p. 4; -@roe].", &opts), Some(ParsedResult{token, rest}));
+
+        let token = Word::Citation { cites: vec!(cite("doe2020", None, false)), in_text: true };
+        let rest = " says";
+        assert_eq!(citation("@doe2020 says", &opts), Some(ParsedResult{token, rest}));
+
+        assert_eq!(citation("[@doe](url)", &opts), None);
+        assert_eq!(citation("[see @doe]", &opts), None);
+        assert_eq!(citation("[@doe] ", &ParserOptions::default()), None);
+    }
+
+    #[test]
+    fn test_citation_start() {
+        assert_eq!(citation_start("As @doe says"), Some(3));
+        assert_eq!(citation_start("mail me@example.com"), None);
+        assert_eq!(citation_start("@doe"), None);
+    }
+
+    #[test]
+    fn test_warnings() {
+        let known = Word::Citation { cites: vec!(cite("known", None, false)), in_text: true };
+        let missing = Word::Citation { cites: vec!(cite("missing", None, false), cite("known", None, false)), in_text: false };
+        let mds = vec!(Md::Sentence(words!(known, missing)));
+        let entries = vec!(Entry { kind: "book".to_string(), key: "known".to_string(), fields: vec!() });
+        assert_eq!(keys(&mds), vec!("known".to_string(), "missing".to_string()));
+        assert_eq!(warnings(&mds, &entries), vec!("unknown citation key: missing".to_string()));
+    }
+}
@@ -0,0 +1,106 @@
+use crate::parser::parser::*;
+use super::attributes::trailing_attributes;
+
+fn indent(line: &str) -> usize {
+    line.len() - line.trim_start_matches(' ').len()
+}
+
+pub(crate) fn fence(line: &str) -> Option<(usize, &str, &str)> {
+    let indent = indent(line);
+    if indent > 3 { return None }
+    let text = &line[indent..];
+    let c = text.chars().next().filter(|c| c == &'`' || c == &'~')?;
+    let len = text.chars().take_while(|x| x == &c).count();
+    if len < 3 { return None }
+    let info = text[len..].trim();
+    if c == '`' && info.contains('`') { return None }
+    Some((indent, &text[..len], info))
+}
+
+pub(crate) fn is_closing(line: &str, fence: &str) -> bool {
+    if indent(line) > 3 { return false }
+    let text = line.trim();
+    let c = fence.chars().next();
+    text.len() >= fence.len() && text.chars().all(|x| Some(x) == c)
+}
+
+fn info_string(info: &str, opts: &ParserOptions) -> (String, Attributes) {
+    let (info, mut attrs) = if opts.attributes {
+        trailing_attributes(info)
+    } else {
+        (info, Attributes::default())
+    };
+    let mut lang = info.split_whitespace().next().unwrap_or("").to_string();
+    if lang.is_empty() && !attrs.classes.is_empty() {
+        lang = attrs.classes.remove(0);
+    }
+    (lang, attrs)
+}
+
+fn strip_indent(line: &str, width: usize) -> &str {
+    &line[width.min(indent(line))..]
+}
+
+pub fn code_block<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    let (line, mut rest) = split_first_pattern(texts, "\n");
+    let (width, fence, info) = fence(line)?;
+    let (lang, attrs) = info_string(info, opts);
+    let mut code = String::new();
+    while !rest.is_empty() {
+        let (line, next) = split_first_pattern(rest, "\n");
+        rest = next;
+        if is_closing(line, fence) { break }
+        code.push_str(strip_indent(line, width));
+        code.push('\n');
+    }
+    let token = Md::Code(CodeBlock { lang, code, attrs, span: Span::default() });
+    Some(ParsedResult::new(token, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fence() {
+        assert_eq!(fence("```rust"), Some((0, "```", "rust")));
+        assert_eq!(fence("  ~~~~ "), Some((2, "~~~~", "")));
+        assert_eq!(fence("``"), None);
+        assert_eq!(fence("``` a`b"), None);
+        assert_eq!(fence("    ```"), None);
+    }
+
+    #[test]
+    fn test_info_string() {
+        let opts = ParserOptions::default();
+        let attrs = Attributes { id: Some("main".to_string()), classes: vec!("numbered".to_string()), pairs: vec!() };
+        assert_eq!(info_string("rust {#main .numbered}", &opts), ("rust".to_string(), attrs));
+
+        let attrs = Attributes { id: None, classes: vec!("numbered".to_string()), pairs: vec!() };
+        assert_eq!(info_string("{.haskell .numbered}", &opts), ("haskell".to_string(), attrs));
+        assert_eq!(info_string("", &opts), ("".to_string(), Attributes::default()));
+
+        let opts = ParserOptions::commonmark();
+        assert_eq!(info_string("rust {#main}", &opts), ("rust".to_string(), Attributes::default()));
+    }
+
+    #[test]
+    fn test_code_block() {
+        let opts = ParserOptions::default();
+        let code = "fn main() {}\n\n  indented\n".to_string();
+        let token = Md::Code(CodeBlock { lang: "rust".to_string(), code, attrs: Attributes::default(), span: Span::default() });
+        let rest = "after";
+        assert_eq!(code_block("```rust\nfn main() {}\n\n  indented\n```\nafter", &opts), Some(ParsedResult{token, rest}));
+
+        let code = " a\n```\n".to_string();
+        let token = Md::Code(CodeBlock { lang: "".to_string(), code, attrs: Attributes::default(), span: Span::default() });
+        let rest = "";
+        assert_eq!(code_block(" ~~~~\n  a\n ```\n ~~~~~", &opts), Some(ParsedResult{token, rest}));
+
+        let code = "open\n".to_string();
+        let token = Md::Code(CodeBlock { lang: "".to_string(), code, attrs: Attributes::default(), span: Span::default() });
+        let rest = "";
+        assert_eq!(code_block("```\nopen", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(code_block("text", &opts), None);
+    }
+}
@@ -0,0 +1,117 @@
+use crate::parser::parser::*;
+use super::attributes::trailing_attributes;
+use super::code::{fence, is_closing as is_closing_fence};
+
+fn colons(line: &str) -> Option<(usize, &str)> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 { return None }
+    let text = &line[indent..];
+    let count = text.chars().take_while(|c| c == &':').count();
+    if count < 3 { return None }
+    Some((count, &text[count..]))
+}
+
+fn opening(line: &str) -> Option<(usize, String, Attributes)> {
+    let (count, text) = colons(line)?;
+    let text = text.trim().trim_end_matches(':').trim_end();
+    let (name, attrs) = trailing_attributes(text);
+    let name = name.trim();
+    if name.contains(char::is_whitespace) { return None }
+    if name.is_empty() && attrs == Attributes::default() { return None }
+    Some((count, name.to_string(), attrs))
+}
+
+fn is_closing(line: &str, count: usize) -> bool {
+    match colons(line) {
+        Some((n, rest)) => n >= count && rest.trim().is_empty(),
+        None => false,
+    }
+}
+
+pub fn container<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.containers { return None }
+    let (line, mut rest) = split_first_pattern(texts, "\n");
+    let (count, name, attrs) = opening(line)?;
+    let mut lines: Vec<&str> = vec!();
+    // Colon counts of the containers opened inside this one, and the fence
+    // of a code block whose lines are left alone.
+    let mut open: Vec<usize> = vec!();
+    let mut code: Option<&str> = None;
+    while !rest.is_empty() {
+        let (line, next) = split_first_pattern(rest, "\n");
+        rest = next;
+        if let Some(marker) = code {
+            if is_closing_fence(line, marker) { code = None }
+        } else if let Some((_, marker, _)) = fence(line) {
+            code = Some(marker);
+        } else if is_closing(line, *open.last().unwrap_or(&count)) {
+            if open.pop().is_none() { break }
+        } else if let Some((n, _, _)) = opening(line) {
+            open.push(n);
+        }
+        lines.push(line);
+    }
+    let body = parse_lines(texts, &lines, opts).ok()?;
+    Some(ParsedResult::new(Md::Container { name, attrs, body, span: Span::default() }, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn container<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::container(texts, opts).without_spans()
+    }
+
+    fn sentence(text: &str) -> Md {
+        Md::Sentence(words!(normal_word!(text)))
+    }
+
+    #[test]
+    fn test_opening() {
+        let attrs = Attributes { id: Some("x".to_string()), classes: vec!("wide".to_string()), pairs: vec!() };
+        assert_eq!(opening("::: warning {#x .wide}"), Some((3, "warning".to_string(), attrs)));
+        assert_eq!(opening(":::: note ::::"), Some((4, "note".to_string(), Attributes::default())));
+
+        let attrs = Attributes { id: None, classes: vec!("aside".to_string()), pairs: vec!() };
+        assert_eq!(opening(":::{.aside}"), Some((3, "".to_string(), attrs)));
+        assert_eq!(opening(":::"), None);
+        assert_eq!(opening("::: two words"), None);
+        assert_eq!(opening(":: note"), None);
+    }
+
+    #[test]
+    fn test_container() {
+        let opts = ParserOptions::default();
+        let body = vec!(sentence("Hello"));
+        let token = Md::Container { name: "note".to_string(), attrs: Attributes::default(), body, span: Span::default() };
+        let rest = "after";
+        assert_eq!(container("::: note\nHello\n:::\nafter", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(container("Hello", &opts), None);
+    }
+
+    #[test]
+    fn test_nested_container() {
+        let opts = ParserOptions::default();
+        let inner = Md::Container { name: "inner".to_string(), attrs: Attributes::default(), body: vec!(sentence("a")), span: Span::default() };
+        let body = vec!(inner, sentence("b"));
+        let token = Md::Container { name: "outer".to_string(), attrs: Attributes::default(), body, span: Span::default() };
+        let rest = "";
+        assert_eq!(container(":::: outer\n::: inner\na\n:::\nb\n::::", &opts), Some(ParsedResult{token, rest}));
+
+        let inner = Md::Container { name: "b".to_string(), attrs: Attributes::default(), body: vec!(sentence("inner")), span: Span::default() };
+        let body = vec!(inner, sentence("still a"));
+        let token = Md::Container { name: "a".to_string(), attrs: Attributes::default(), body, span: Span::default() };
+        assert_eq!(container("::: a\n::: b\ninner\n:::\nstill a\n:::", &opts), Some(ParsedResult{token, rest}));
+    }
+
+    #[test]
+    fn test_fenced_container() {
+        let opts = ParserOptions::default();
+        let code = Md::Code(CodeBlock { lang: "".to_string(), attrs: Attributes::default(), code: ":::\n".to_string(), span: Span::default() });
+        let token = Md::Container { name: "a".to_string(), attrs: Attributes::default(), body: vec!(code), span: Span::default() };
+        let rest = "after";
+        assert_eq!(container("::: a\n```\n:::\n```\n:::\nafter", &opts), Some(ParsedResult{token, rest}));
+    }
+}
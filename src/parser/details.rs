@@ -0,0 +1,142 @@
+use crate::parser::parser::*;
+use super::sentence::words;
+use crate::{words, normal_word};
+
+fn body_line(line: &str) -> Option<&str> {
+    if line.trim().is_empty() { return Some(&line[line.len()..]) }
+    consume(line, "    ").or_else(|| consume(line, "\t"))
+}
+
+pub(crate) fn indented_lines(mut texts: &str) -> ParsedResult<'_, Vec<&str>> {
+    let mut lines: Vec<&str> = vec!();
+    let mut rest = texts;
+    while !texts.is_empty() {
+        let (line, next) = split_first_pattern(texts, "\n");
+        let Some(line) = body_line(line) else { break };
+        lines.push(line);
+        texts = next;
+        if !line.is_empty() { rest = texts }
+    }
+    while lines.last() == Some(&"") { lines.pop(); }
+    ParsedResult::new(lines, rest)
+}
+
+/// The summary words, with `title` a slice of `texts`.
+fn summary(texts: &str, title: &str, opts: &ParserOptions) -> Option<Words> {
+    let title = title.trim();
+    let title = title.strip_prefix('"').and_then(|t| t.strip_suffix('"')).unwrap_or(title);
+    if title.is_empty() { return Some(words!(normal_word!("Details"))) }
+    let mut words = words(title, opts)?;
+    shift_words(&mut words, offset_in(texts, title));
+    Some(words)
+}
+
+fn fold<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    let (line, rest) = split_first_pattern(texts, "\n");
+    let text = consume(line, "???")?;
+    let (open, title) = match consume(text, "+") {
+        Some(title) => (true, title),
+        None => (false, text),
+    };
+    if !title.is_empty() && !title.starts_with(' ') { return None }
+    let lines = indented_lines(rest);
+    let body = parse_lines(texts, &lines.token, opts).ok()?;
+    let token = Md::Details { summary: summary(texts, title, opts)?, open, body, span: Span::default() };
+    Some(ParsedResult::new(token, lines.rest))
+}
+
+fn details_tag(line: &str) -> Option<bool> {
+    let text = consume(line.trim(), "<details")?;
+    let text = text.strip_suffix('>')?;
+    match text.trim() {
+        "" => Some(false),
+        "open" => Some(true),
+        _ => None,
+    }
+}
+
+fn summary_tag(line: &str) -> Option<&str> {
+    consume(line.trim(), "<summary>")?.strip_suffix("</summary>")
+}
+
+fn html_details<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    let (line, mut rest) = split_first_pattern(texts, "\n");
+    let open = details_tag(line)?;
+    let mut title = "";
+    let (first, next) = split_first_pattern(rest, "\n");
+    if let Some(text) = summary_tag(first) {
+        title = text;
+        rest = next;
+    }
+    let mut lines: Vec<&str> = vec!();
+    let mut depth = 0;
+    let mut closed = false;
+    while !rest.is_empty() {
+        let (line, next) = split_first_pattern(rest, "\n");
+        rest = next;
+        if details_tag(line).is_some() { depth += 1 }
+        if line.trim() == "</details>" {
+            if depth == 0 { closed = true; break }
+            depth -= 1;
+        }
+        lines.push(line);
+    }
+    if !closed { return None }
+    let body = parse_lines(texts, &lines, opts).ok()?;
+    let token = Md::Details { summary: summary(texts, title, opts)?, open, body, span: Span::default() };
+    Some(ParsedResult::new(token, rest))
+}
+
+pub fn details<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.containers { return None }
+    fold(texts, opts).or_else(|| html_details(texts, opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn details<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::details(texts, opts).without_spans()
+    }
+
+    fn sentence(text: &str) -> Md {
+        Md::Sentence(words!(normal_word!(text)))
+    }
+
+    #[test]
+    fn test_indented_lines() {
+        let lines = indented_lines("    a\n\n\tb\n\nc");
+        assert_eq!(lines, ParsedResult { token: vec!("a", "", "b"), rest: "\nc" });
+    }
+
+    #[test]
+    fn test_fold() {
+        let opts = ParserOptions::default();
+        let body = vec!(sentence("Restart it."), sentence(""), sentence("Then retry."));
+        let token = Md::Details { summary: words!(normal_word!("It hangs")), open: true, body, span: Span::default() };
+        let rest = "\nafter";
+        assert_eq!(details("???+ \"It hangs\"\n    Restart it.\n\n    Then retry.\n\nafter", &opts), Some(ParsedResult{token, rest}));
+
+        let token = Md::Details { summary: words!(normal_word!("Details")), open: false, body: vec!(), span: Span::default() };
+        let rest = "after";
+        assert_eq!(details("???\nafter", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(details("???what", &opts), None);
+    }
+
+    #[test]
+    fn test_html_details() {
+        let opts = ParserOptions::default();
+        let inner = Md::Details { summary: words!(normal_word!("Details")), open: true, body: vec!(sentence("b")), span: Span::default() };
+        let body = vec!(sentence("a"), inner);
+        let summary = words!(Word::Bold(words!(normal_word!("Logs"))));
+        let token = Md::Details { summary, open: false, body, span: Span::default() };
+        let rest = "after";
+        let text = "<details>\n<summary>**Logs**</summary>\na\n<details open>\nb\n</details>\n</details>\nafter";
+        assert_eq!(details(text, &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(details("<details class=\"x\">", &opts), None);
+        assert_eq!(details("<details>foo\n</details>", &opts), None);
+        assert_eq!(details("<details>\nbody\n\n# Heading later", &opts), None);
+        assert_eq!(details("<details>\n<details>\nb\n</details>", &opts), None);
+    }
+}
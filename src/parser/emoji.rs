@@ -0,0 +1,87 @@
+use crate::parser::parser::*;
+use super::emoji_table::SHORTCODES;
+
+pub fn lookup(shortcode: &str) -> Option<&'static str> {
+    let index = SHORTCODES.binary_search_by_key(&shortcode, |(code, _)| code).ok()?;
+    Some(SHORTCODES[index].1)
+}
+
+fn is_shortcode(text: &str) -> bool {
+    !text.is_empty() && text.chars().all(|c| c.is_ascii_alphanumeric() || "_+-".contains(c))
+}
+
+fn split(text: &str) -> Option<Vec<(usize, Word)>> {
+    let mut tokens: Vec<(usize, Word)> = vec!();
+    let (mut plain, mut rest) = (String::new(), text);
+    while let Some(start) = rest.find(':') {
+        let after = &rest[(start+1)..];
+        let found = after.find(':')
+            .map(|end| &after[..end])
+            .filter(|code| is_shortcode(code))
+            .and_then(|code| Some((code, lookup(code)?)));
+        let Some((code, char)) = found else {
+            plain.push_str(&rest[..=start]);
+            rest = after;
+            continue;
+        };
+        plain.push_str(&rest[..start]);
+        let at = text.len() - rest.len() + start;
+        if !plain.is_empty() {
+            tokens.push((at - plain.len(), Word::Normal(std::mem::take(&mut plain))));
+        }
+        tokens.push((at, Word::Emoji { shortcode: code.to_string(), char: char.to_string() }));
+        rest = &after[(code.len()+1)..];
+    }
+    if tokens.is_empty() { return None }
+    plain.push_str(rest);
+    if !plain.is_empty() {
+        tokens.push((text.len() - plain.len(), Word::Normal(plain)));
+    }
+    Some(tokens)
+}
+
+pub fn shortcodes(mds: &mut [Md]) {
+    walk_words_mut(mds, &mut |words| split_normal(words, &mut split));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn emoji(shortcode: &str, char: &str) -> Word {
+        Word::Emoji { shortcode: shortcode.to_string(), char: char.to_string() }
+    }
+
+    #[test]
+    fn test_lookup() {
+        assert_eq!(lookup("rocket"), Some("🚀"));
+        assert_eq!(lookup("+1"), Some("👍"));
+        assert_eq!(lookup("white_check_mark"), Some("✅"));
+        assert_eq!(lookup("not_an_emoji"), None);
+    }
+
+    #[test]
+    fn test_split() {
+        let token = vec!((0, normal_word!("Ship it ")), (8, emoji("rocket", "🚀")), (16, normal_word!("!")));
+        assert_eq!(split("Ship it :rocket:!"), Some(token));
+
+        let token = vec!((0, normal_word!("at 10:30 ")), (9, emoji("+1", "👍")), (13, emoji("tada", "🎉")));
+        assert_eq!(split("at 10:30 :+1::tada:"), Some(token));
+
+        assert_eq!(split(":unknown: code"), None);
+        assert_eq!(split("a: b: c"), None);
+    }
+
+    #[test]
+    fn test_shortcodes() {
+        let code = Word::Code(":rocket:".to_string());
+        let bold = Word::Bold(words!(normal_word!(":tada:")));
+        let mut mds = vec!(Md::Sentence(words!(code, bold)));
+        shortcodes(&mut mds);
+
+        let code = Word::Code(":rocket:".to_string());
+        let bold = Word::Bold(words!(emoji("tada", "🎉")));
+        assert_eq!(mds, vec!(Md::Sentence(words!(code, bold))));
+    }
+}
@@ -0,0 +1,68 @@
+use std::fmt;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ParseErrorKind {
+    /// No block parser accepted the input.
+    UnexpectedInput,
+    /// A parser accepted the input without consuming any of it.
+    NoProgress,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseError {
+    /// 1-based line of the block that failed.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+    pub kind: ParseErrorKind,
+}
+
+impl ParseError {
+    /// An error at the start of `rest`, which must be a suffix of `source`.
+    pub fn at(source: &str, rest: &str, kind: ParseErrorKind) -> ParseError {
+        let mut offset = source.len().saturating_sub(rest.len());
+        while !source.is_char_boundary(offset) { offset -= 1 }
+        let consumed = &source[..offset];
+        let line = consumed.matches('\n').count() + 1;
+        let column = consumed.rsplit('\n').next().unwrap_or("").chars().count() + 1;
+        ParseError { line, column, kind }
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.kind {
+            ParseErrorKind::UnexpectedInput => "unexpected input",
+            ParseErrorKind::NoProgress => "parser made no progress",
+        };
+        write!(f, "line {}, column {}: {}", self.line, self.column, kind)
+    }
+}
+
+impl std::error::Error for ParseError {}
+
+/// A name given to `Parser::disable` that no block parser has.
+#[derive(Debug, PartialEq, Clone)]
+pub struct UnknownParser(pub String);
+
+impl fmt::Display for UnknownParser {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "no block parser called `{}`", self.0)
+    }
+}
+
+impl std::error::Error for UnknownParser {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_at() {
+        let source = "one\ntwö\nthree";
+        let error = ParseError::at(source, &source[8..], ParseErrorKind::NoProgress);
+        assert_eq!(error, ParseError { line: 2, column: 4, kind: ParseErrorKind::NoProgress });
+        assert_eq!(error.to_string(), "line 2, column 4: parser made no progress");
+        assert_eq!(ParseError::at(source, source, ParseErrorKind::UnexpectedInput).line, 1);
+    }
+}
@@ -0,0 +1,80 @@
+use crate::parser::parser::*;
+use super::details::indented_lines;
+
+/// The label of a leading `[^label]`, and the text after it.
+fn label(text: &str) -> Option<(&str, &str)> {
+    let text = consume(text, "[^")?;
+    let end = text.find(']')?;
+    let label = &text[..end];
+    if label.is_empty() || label.contains(char::is_whitespace) { return None }
+    Some((label, &text[(end+1)..]))
+}
+
+/// `[^label]: text`, continued by lines indented four spaces or a tab.
+pub fn footnote<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.footnotes { return None }
+    let (line, rest) = split_first_pattern(texts, "\n");
+    let (label, text) = label(line)?;
+    let text = consume(text, ":")?.trim_start();
+    let indented = indented_lines(rest);
+    let lines: Vec<&str> = std::iter::once(text).filter(|text| !text.is_empty()).chain(indented.token).collect();
+    let body = parse_lines(texts, &lines, opts).ok()?;
+    let token = Md::Footnote { label: label.to_string(), body, span: Span::default() };
+    Some(ParsedResult::new(token, indented.rest))
+}
+
+/// A `[^label]` reference.
+pub(crate) fn footnote_ref<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.footnotes { return None }
+    let (label, rest) = label(text)?;
+    Some(ParsedResult::new(Word::Footnote { label: label.to_string() }, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn footnote<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::footnote(texts, opts).without_spans()
+    }
+
+    fn words(source: &str, opts: &ParserOptions) -> Option<Words> {
+        super::super::sentence::words(source, opts).without_spans()
+    }
+
+    fn sentence(text: &str) -> Md {
+        Md::Sentence(words!(normal_word!(text)))
+    }
+
+    #[test]
+    fn test_label() {
+        assert_eq!(label("[^1]: a"), Some(("1", ": a")));
+        assert_eq!(label("[^note] b"), Some(("note", " b")));
+        assert_eq!(label("[^]"), None);
+        assert_eq!(label("[^a b]"), None);
+        assert_eq!(label("[1]"), None);
+    }
+
+    #[test]
+    fn test_footnote() {
+        let opts = ParserOptions::gfm();
+        let token = Md::Footnote { label: "1".to_string(), body: vec!(sentence("First"), sentence("Second")), span: Span::default() };
+        let rest = "after";
+        assert_eq!(footnote("[^1]: First\n    Second\nafter", &opts), Some(ParsedResult{token, rest}));
+
+        let mds = parse_with("[^n]:\n    Body", &opts).unwrap();
+        assert_eq!(mds[0].children()[0][0].span().range(), 10..14);
+        assert_eq!(mds.without_spans(), vec!(Md::Footnote { label: "n".to_string(), body: vec!(sentence("Body")), span: Span::default() }));
+
+        assert_eq!(footnote("[^1]: First", &ParserOptions::default()), None);
+        assert_eq!(footnote("[^1] First", &opts), None);
+    }
+
+    #[test]
+    fn test_footnote_ref() {
+        let opts = ParserOptions::gfm();
+        let token = Some(words!(normal_word!("See"), Word::Footnote { label: "1".to_string() }, normal_word!(".")));
+        assert_eq!(words("See[^1].", &opts), token);
+    }
+}
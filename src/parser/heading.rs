@@ -1,7 +1,8 @@
 use crate::parser::parser::*;
 use super::sentence::words;
+use super::attributes::trailing_attributes;
 
-pub fn heading(texts: &str) -> Option<ParsedResult<Md>> {
+pub fn heading<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
     ["#", "##", "###"].iter().find_map(|p| {
         let (text, rest) = if let Some(n) = texts.find("\n") {
             (&texts[..n], &texts[(n+1)..])
@@ -10,8 +11,15 @@ pub fn heading(texts: &str) -> Option<ParsedResult<Md>> {
         };
         let text = consume(text, p)?;
         let text = space(text)?;
-        let tokens = words(&text);
-        let token = Md::Heading(p.len(), tokens);
+        let (text, mut attrs) = if opts.attributes {
+            trailing_attributes(text)
+        } else {
+            (text, Attributes::default())
+        };
+        let id = attrs.id.take().unwrap_or_default();
+        let mut words = words(text, opts)?;
+        shift_words(&mut words, offset_in(texts, text));
+        let token = Md::Heading(Heading { level: p.len(), words, id, attrs, span: Span::default() });
         Some(ParsedResult::new(token, rest))
     })
 }
@@ -21,30 +29,45 @@ mod tests {
     use super::*;
     use crate::{words,normal_word};
 
+    fn heading<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::heading(texts, opts).without_spans()
+    }
+
     #[test]
     fn test_heading() {
         let words = words!(normal_word!("Hello World!"));
-        let token = Md::Heading(1, words);
-        assert_eq!(heading(&"# Hello World!"), Some(ParsedResult{token, rest: ""}));
+        let token = Md::Heading(Heading { level: 1, words, id: "".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("# Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
 
         let words = words!(normal_word!("Hello World!"));
-        let token = Md::Heading(1, words);
-        assert_eq!(heading(&"#    Hello World!"), Some(ParsedResult{token, rest: ""}));
+        let token = Md::Heading(Heading { level: 1, words, id: "".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("#    Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
 
         let words = words!(normal_word!("Hello World!"));
-        let token = Md::Heading(2, words);
-        assert_eq!(heading(&"## Hello World!"), Some(ParsedResult{token, rest: ""}));
+        let token = Md::Heading(Heading { level: 2, words, id: "".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("## Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
 
         let words = words!(normal_word!("Hello World!"));
-        let token = Md::Heading(3, words);
-        assert_eq!(heading(&"### Hello World!"), Some(ParsedResult{token, rest: ""}));
+        let token = Md::Heading(Heading { level: 3, words, id: "".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("### Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
     }
 
     #[test]
     fn test_heading_multiline() {
         let words = words!(normal_word!("Hello "));
-        let token = Md::Heading(1, words);
-        assert_eq!(heading(&"# Hello \nWorld!"), Some(ParsedResult{token, rest: "World!"}));
+        let token = Md::Heading(Heading { level: 1, words, id: "".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("# Hello \nWorld!", &ParserOptions::default()), Some(ParsedResult{token, rest: "World!"}));
     }
 
-}
\ No newline at end of file
+    #[test]
+    fn test_heading_attributes() {
+        let words = words!(normal_word!("Hello World!"));
+        let token = Md::Heading(Heading { level: 2, words, id: "greeting".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(heading("## Hello World! {#greeting}", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
+
+        let words = words!(normal_word!("Hello"));
+        let attrs = Attributes { id: None, classes: vec!("wide".to_string()), pairs: vec!(("lang".to_string(), "en".to_string())) };
+        let token = Md::Heading(Heading { level: 1, words, id: "top".to_string(), attrs, span: Span::default() });
+        assert_eq!(heading("# Hello {#top .wide lang=en}", &ParserOptions::default()), Some(ParsedResult{token, rest: ""}));
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use crate::parser::parser::*;
+use super::heading::heading;
+use super::slug::{slugify, words_to_text};
+
+pub trait FileSystem {
+    fn read(&self, path: &Path) -> io::Result<String>;
+}
+
+/// Reads included files from disk.
+pub struct Disk;
+
+impl FileSystem for Disk {
+    fn read(&self, path: &Path) -> io::Result<String> {
+        fs::read_to_string(path)
+    }
+}
+
+#[derive(Debug)]
+pub enum IncludeError {
+    Io { path: PathBuf, error: io::Error },
+    /// The chain of files that leads back to the first one.
+    Cycle(Vec<PathBuf>),
+    Anchor { path: PathBuf, anchor: String },
+    Parse(ParseError),
+}
+
+impl fmt::Display for IncludeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            IncludeError::Io { path, error } => write!(f, "cannot include {}: {}", path.display(), error),
+            IncludeError::Cycle(chain) => {
+                let chain: Vec<String> = chain.iter().map(|path| path.display().to_string()).collect();
+                write!(f, "include cycle: {}", chain.join(" -> "))
+            },
+            IncludeError::Anchor { path, anchor } => write!(f, "no heading #{} in {}", anchor, path.display()),
+            IncludeError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for IncludeError {}
+
+#[derive(Debug, PartialEq)]
+enum Selection<'a> {
+    All,
+    /// 1-based, inclusive line range.
+    Lines(usize, Option<usize>),
+    /// A heading id and the section beneath it.
+    Anchor(&'a str),
+}
+
+fn directive(line: &str) -> Option<(&str, Selection<'_>)> {
+    let text = consume(line.trim(), "{{#include")?.strip_suffix("}}")?;
+    if !text.starts_with(char::is_whitespace) { return None }
+    let text = text.trim();
+    if let Some((path, anchor)) = text.split_once('#') {
+        return Some((path, Selection::Anchor(anchor)))
+    }
+    let Some((path, range)) = text.split_once(':') else { return Some((text, Selection::All)) };
+    let (start, end) = range.split_once(':').unwrap_or((range, ""));
+    let start = if start.is_empty() { 1 } else { start.parse().ok()? };
+    let end = if end.is_empty() { None } else { Some(end.parse().ok()?) };
+    Some((path, Selection::Lines(start, end)))
+}
+
+/// Resolves `.` and `..` without touching the filesystem, so the same file
+/// reached by two routes is recognised in cycle checks.
+fn normalize(path: &Path) -> PathBuf {
+    let mut normal = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {},
+            Component::ParentDir if normal.file_name().is_some() => { normal.pop(); },
+            component => normal.push(component),
+        }
+    }
+    normal
+}
+
+fn section(text: &str, anchor: &str, opts: &ParserOptions) -> Option<String> {
+    let lines: Vec<&str> = text.lines().collect();
+    let parsed = |line: &str| match heading(line, opts)?.token {
+        Md::Heading(heading) => Some(heading),
+        _ => None,
+    };
+    let start = lines.iter().position(|line| parsed(line).is_some_and(|heading| {
+        let id = if heading.id.is_empty() { slugify(&words_to_text(&heading.words)) } else { heading.id };
+        id == anchor
+    }))?;
+    let depth = parsed(lines[start])?.level;
+    let end = lines[(start+1)..].iter()
+        .position(|line| parsed(line).is_some_and(|heading| heading.level <= depth))
+        .map_or(lines.len(), |n| start + 1 + n);
+    Some(lines[start..end].join("\n"))
+}
+
+fn select(text: &str, selection: &Selection, path: &Path, opts: &ParserOptions) -> Result<String, IncludeError> {
+    match selection {
+        Selection::All => Ok(text.trim_end_matches('\n').to_string()),
+        Selection::Lines(start, end) => {
+            let lines: Vec<&str> = text.lines()
+                .skip(start.saturating_sub(1))
+                .take(end.map_or(usize::MAX, |end| (end + 1).saturating_sub(*start)))
+                .collect();
+            Ok(lines.join("\n"))
+        },
+        Selection::Anchor(anchor) => section(text, anchor, opts).ok_or_else(|| {
+            IncludeError::Anchor { path: path.to_path_buf(), anchor: anchor.to_string() }
+        }),
+    }
+}
+
+fn expand_in(
+    text: &str,
+    path: &Path,
+    stack: &mut Vec<PathBuf>,
+    fs: &dyn FileSystem,
+    opts: &ParserOptions
+) -> Result<String, IncludeError> {
+    let mut lines: Vec<String> = vec!();
+    for line in text.lines() {
+        let Some((target, selection)) = directive(line) else {
+            lines.push(line.to_string());
+            continue
+        };
+        let target = normalize(&path.parent().unwrap_or(Path::new("")).join(target));
+        if stack.contains(&target) {
+            let mut chain = stack.clone();
+            chain.push(target);
+            return Err(IncludeError::Cycle(chain))
+        }
+        let included = fs.read(&target).map_err(|error| IncludeError::Io { path: target.clone(), error })?;
+        let included = select(&included, &selection, &target, opts)?;
+        stack.push(target.clone());
+        lines.push(expand_in(&included, &target, stack, fs, opts)?);
+        stack.pop();
+    }
+    Ok(lines.join("\n"))
+}
+
+/// Replaces `{{#include path.md}}` lines in `text`, which was read from
+/// `path`. Targets may select lines as `path.md:3:10` or a heading section
+/// as `path.md#heading-id`, and are resolved relative to the including file.
+pub fn expand(text: &str, path: &Path, fs: &dyn FileSystem, opts: &ParserOptions) -> Result<String, IncludeError> {
+    expand_in(text, path, &mut vec!(normalize(path)), fs, opts)
+}
+
+/// Reads, expands and parses `path` with `opts`. Spans refer to the
+/// expanded text.
+pub fn parse_file(path: &Path, fs: &dyn FileSystem, opts: &ParserOptions) -> Result<Vec<Md>, IncludeError> {
+    let text = fs.read(path).map_err(|error| IncludeError::Io { path: path.to_path_buf(), error })?;
+    parse_with(&expand(&text, path, fs, opts)?, opts).map_err(IncludeError::Parse)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    struct Memory(HashMap<PathBuf, &'static str>);
+
+    impl FileSystem for Memory {
+        fn read(&self, path: &Path) -> io::Result<String> {
+            self.0.get(path).map(|text| text.to_string()).ok_or(io::Error::from(io::ErrorKind::NotFound))
+        }
+    }
+
+    fn memory(files: &[(&str, &'static str)]) -> Memory {
+        Memory(files.iter().map(|(path, text)| (PathBuf::from(path), *text)).collect())
+    }
+
+    #[test]
+    fn test_directive() {
+        assert_eq!(directive("{{#include a.md}}"), Some(("a.md", Selection::All)));
+        assert_eq!(directive(" {{#include a.md:2:4}} "), Some(("a.md", Selection::Lines(2, Some(4)))));
+        assert_eq!(directive("{{#include a.md::4}}"), Some(("a.md", Selection::Lines(1, Some(4)))));
+        assert_eq!(directive("{{#include a.md:3}}"), Some(("a.md", Selection::Lines(3, None))));
+        assert_eq!(directive("{{#include a.md#setup}}"), Some(("a.md", Selection::Anchor("setup"))));
+        assert_eq!(directive("{{#include a.md:x}}"), None);
+        assert_eq!(directive("{{#includea.md}}"), None);
+        assert_eq!(directive("see {{#include a.md}}"), None);
+    }
+
+    #[test]
+    fn test_normalize() {
+        assert_eq!(normalize(Path::new("docs/./a/../b.md")), PathBuf::from("docs/b.md"));
+        assert_eq!(normalize(Path::new("../b.md")), PathBuf::from("../b.md"));
+    }
+
+    #[test]
+    fn test_expand() {
+        let opts = ParserOptions::default();
+        let fs = memory(&[
+            ("docs/parts/a.md", "one\ntwo\nthree\n"),
+            ("docs/parts/b.md", "{{#include a.md:2:2}}\n# Setup\nrun\n## Detail\nmore\n# Next\nskip"),
+        ]);
+        let text = "start\n{{#include parts/a.md::2}}\n{{#include parts/b.md#setup}}\nend";
+        assert_eq!(expand(text, Path::new("docs/index.md"), &fs, &opts).unwrap(), "start\none\ntwo\n# Setup\nrun\n## Detail\nmore\nend");
+    }
+
+    #[test]
+    fn test_expand_errors() {
+        let opts = ParserOptions::default();
+        let fs = memory(&[
+            ("a.md", "{{#include sub/b.md}}"),
+            ("sub/b.md", "{{#include ../a.md}}"),
+            ("c.md", "# Only"),
+        ]);
+        let error = expand("{{#include sub/b.md}}", Path::new("a.md"), &fs, &opts).unwrap_err();
+        assert_eq!(error.to_string(), "include cycle: a.md -> sub/b.md -> a.md");
+
+        let error = expand("{{#include missing.md}}", Path::new("a.md"), &fs, &opts).unwrap_err();
+        assert!(matches!(error, IncludeError::Io { path, .. } if path == Path::new("missing.md")));
+
+        let error = expand("{{#include c.md#nope}}", Path::new("a.md"), &fs, &opts).unwrap_err();
+        assert_eq!(error.to_string(), "no heading #nope in c.md");
+    }
+}
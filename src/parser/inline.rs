@@ -0,0 +1,86 @@
+use crate::parser::parser::*;
+use super::sentence::delimited;
+
+/// An inline syntax. Plain text is split before each of its triggers, and
+/// `parse` is tried wherever one of them starts the text, before the built-in
+/// emphasis and links; text it rejects stays plain.
+pub trait InlineParser: Send + Sync {
+    fn triggers(&self) -> Vec<&str>;
+    fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>>;
+}
+
+/// Text between `open` and `close`, such as `||spoiler||`, parsed as a
+/// `Word::Extension` called `name`.
+pub struct Delimited {
+    pub name: String,
+    pub open: String,
+    pub close: String,
+}
+
+impl Delimited {
+    pub fn new(name: &str, open: &str, close: &str) -> Delimited {
+        Delimited { name: name.to_string(), open: open.to_string(), close: close.to_string() }
+    }
+}
+
+impl InlineParser for Delimited {
+    fn triggers(&self) -> Vec<&str> {
+        vec!(&self.open)
+    }
+
+    fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+        let extension = |words| Word::Extension { name: self.name.clone(), words };
+        delimited(text, &self.open, &self.close, &extension, opts)
+    }
+}
+
+/// The registered parsers whose triggers start `text`.
+pub(crate) fn inline_word<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    opts.extensions.inlines.iter()
+        .filter(|parser| parser.triggers().iter().any(|trigger| text.starts_with(trigger)))
+        .find_map(|parser| parser.parse(text, opts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn words(source: &str, opts: &ParserOptions) -> Option<Words> {
+        super::super::sentence::words(source, opts).without_spans()
+    }
+
+    fn opts() -> ParserOptions {
+        let parser = Parser::default()
+            .inline(Delimited::new("spoiler", "||", "||"))
+            .inline(Delimited::new("ins", "{+", "+}"));
+        parser.options().clone()
+    }
+
+    fn extension(name: &str, words: Words) -> Word {
+        Word::Extension { name: name.to_string(), words }
+    }
+
+    #[test]
+    fn test_delimited() {
+        let opts = opts();
+        let token = Some(words!(
+            normal_word!("a"),
+            extension("spoiler", words!(Word::Bold(words!(normal_word!("b"))))),
+            normal_word!(" "),
+            extension("ins", words!(normal_word!("c")))
+        ));
+        assert_eq!(words("a||**b**|| {+c+}", &opts), token);
+
+        let parsed = super::super::sentence::words("x ||y||", &opts).unwrap();
+        let Word::Extension { words: inner, .. } = &parsed.0[1] else { panic!("expected an extension") };
+        assert_eq!((parsed.1[1].range(), inner.1[0].range()), (2..7, 4..5));
+    }
+
+    #[test]
+    fn test_unclosed() {
+        let opts = opts();
+        assert_eq!(words("a || b", &opts), Some(words!(normal_word!("a "), normal_word!("||"), normal_word!(" b"))));
+        assert_eq!(words("`||x||`", &opts), Some(words!(Word::Code("||x||".to_string()))));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::parser::parser::*;
+use super::sentence::words;
+use super::attributes::attributes;
+
+pub(crate) fn bracket(text: &str) -> Option<(&str, &str)> {
+    let text = consume(text, "[")?;
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some((&text[..i], &text[(i+1)..])),
+            ']' => depth -= 1,
+            _ => {},
+        }
+    }
+    None
+}
+
+fn title(text: &str) -> Option<(Option<String>, &str)> {
+    let text = text.trim_start();
+    let close = match text.chars().next()? {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => return Some((None, text)),
+    };
+    let body = &text[1..];
+    let end = body.find(close)?;
+    Some((Some(body[..end].to_string()), &body[(end+1)..]))
+}
+
+fn destination(text: &str) -> Option<(String, Option<String>, &str)> {
+    let text = consume(text, "(")?.trim_start();
+    let (url, rest) = if let Some(text) = consume(text, "<") {
+        let end = text.find('>')?;
+        (&text[..end], &text[(end+1)..])
+    } else {
+        let mut depth = 0;
+        let end = text.char_indices().find_map(|(i, c)| match c {
+            '(' => { depth += 1; None },
+            ')' if depth == 0 => Some(i),
+            ')' => { depth -= 1; None },
+            c if c.is_whitespace() => Some(i),
+            _ => None,
+        }).unwrap_or(text.len());
+        (&text[..end], &text[end..])
+    };
+    let (title, rest) = title(rest)?;
+    let rest = consume(rest.trim_start(), ")")?;
+    Some((url.to_string(), title, rest))
+}
+
+fn trailing<'a>(text: &'a str, opts: &ParserOptions) -> (Attributes, &'a str) {
+    if !opts.attributes { return (Attributes::default(), text) }
+    match attributes(text) {
+        Some(attrs) => (attrs.token, attrs.rest),
+        None => (Attributes::default(), text),
+    }
+}
+
+pub(crate) fn link<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let (inner, rest) = bracket(text)?;
+    let inner_words = || {
+        let mut words = words(inner, opts)?;
+        shift_words(&mut words, offset_in(text, inner));
+        Some(words)
+    };
+    if let Some((url, title, rest)) = destination(rest) {
+        let (attrs, rest) = trailing(rest, opts);
+        let token = Word::Link { words: inner_words()?, url, title, attrs };
+        return Some(ParsedResult::new(token, rest))
+    }
+    if !opts.attributes { return None }
+    let attrs = attributes(rest)?;
+    let token = Word::Span { words: inner_words()?, attrs: attrs.token };
+    Some(ParsedResult::new(token, attrs.rest))
+}
+
+pub(crate) fn image<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let text = consume(text, "!")?;
+    let (alt, rest) = bracket(text)?;
+    let (url, title, rest) = destination(rest)?;
+    let (attrs, rest) = trailing(rest, opts);
+    let token = Word::Image { alt: alt.to_string(), url, title, attrs };
+    Some(ParsedResult::new(token, rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn link<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+        super::link(text, opts).without_spans()
+    }
+
+    #[test]
+    fn test_bracket() {
+        assert_eq!(bracket("[a [b] c](x)"), Some(("a [b] c", "(x)")));
+        assert_eq!(bracket("[a \\] b]"), Some(("a \\] b", "")));
+        assert_eq!(bracket("[open"), None);
+    }
+
+    #[test]
+    fn test_destination() {
+        assert_eq!(destination("(https://a.b/c)d"), Some(("https://a.b/c".to_string(), None, "d")));
+        assert_eq!(destination("(/x \"The title\")"), Some(("/x".to_string(), Some("The title".to_string()), "")));
+        assert_eq!(destination("(<a b> 'T')"), Some(("a b".to_string(), Some("T".to_string()), "")));
+        assert_eq!(destination("(/wiki/Rust_(lang))"), Some(("/wiki/Rust_(lang)".to_string(), None, "")));
+        assert_eq!(destination("(/x y)"), None);
+    }
+
+    #[test]
+    fn test_link() {
+        let opts = ParserOptions::default();
+        let words = words!(Word::Bold(words!(normal_word!("Rust"))));
+        let attrs = Attributes { id: None, classes: vec!("ext".to_string()), pairs: vec!() };
+        let token = Word::Link { words, url: "https://rust-lang.org".to_string(), title: None, attrs };
+        let rest = "!";
+        assert_eq!(link("[**Rust**](https://rust-lang.org){.ext}!", &opts), Some(ParsedResult{token, rest}));
+
+        let words = words!(normal_word!("text"));
+        let attrs = Attributes { id: None, classes: vec!("smallcaps".to_string()), pairs: vec!() };
+        let token = Word::Span { words, attrs };
+        let rest = "";
+        assert_eq!(link("[text]{.smallcaps}", &opts), Some(ParsedResult{token, rest}));
+
+        assert_eq!(link("[text] plain", &opts), None);
+    }
+
+    #[test]
+    fn test_image() {
+        let opts = ParserOptions::default();
+        let attrs = Attributes { id: None, classes: vec!(), pairs: vec!(("width".to_string(), "50".to_string())) };
+        let token = Word::Image { alt: "logo".to_string(), url: "logo.png".to_string(), title: Some("Logo".to_string()), attrs };
+        let rest = "";
+        assert_eq!(image("![logo](logo.png \"Logo\"){width=50}", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(image("![logo]", &opts), None);
+    }
+}
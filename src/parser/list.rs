@@ -1,157 +1,397 @@
 use crate::parser::parser::*;
-use super::sentence::words;
-use crate::items;
+use super::span::shift;
 
-fn count_tab(texts: &str) -> usize {
-    texts.chars().take_while(|c| c ==&' ' ).count()/2
+const TAB_STOP: usize = 4;
+
+fn advance(column: usize, c: char) -> usize {
+    if c == '\t' {
+        column + TAB_STOP - column % TAB_STOP
+    } else {
+        column + 1
+    }
 }
 
-fn item(texts: &str, tab_num: usize) -> Option<ParsedResult<Item>> {
-    let (text, rest) = split_first_pattern(texts, "\n");
-    let text = text.trim_start();
+fn count_indent(texts: &str) -> usize {
+    texts.chars()
+        .take_while(|c| c == &' ' || c == &'\t')
+        .fold(0, advance)
+}
+
+#[derive(Debug, PartialEq)]
+struct Marker<'a> {
+    column: usize,
+    content: usize,
+    text: &'a str,
+}
+
+fn marker(line: &str) -> Option<Marker<'_>> {
+    let column = count_indent(line);
+    let text = line.trim_start_matches([' ', '\t']);
     let text = consume(text, "-")?;
-    let text = space(text)?;
-    let words = words(&text);
-    let space_num = count_tab(&rest);
-    let (i, rest) = if space_num <= tab_num {
-        (items!(), rest)
-    } else {
-        let c = items(&rest, space_num);
-        (c.token, c.rest)
+    let spaces: String = text.chars()
+        .take_while(|c| c == &' ' || c == &'\t')
+        .collect();
+    if spaces.is_empty() { return None }
+    let end = spaces.chars().fold(column + 1, advance);
+    let content = if end - column - 1 <= 4 { end } else { column + 2 };
+    let text = &text[spaces.len()..];
+    Some(Marker { column, content, text })
+}
+
+fn child_column(marker: &Marker, opts: &ParserOptions) -> usize {
+    match opts.list_indent {
+        ListIndent::ContentColumn => marker.content,
+        ListIndent::TwoSpaces => (marker.column / 2 + 1) * 2,
+        ListIndent::FourSpaces => (marker.column / 4 + 1) * 4,
+    }
+}
+
+fn is_blank(line: &str) -> bool {
+    line.trim().is_empty()
+}
+
+fn dedent(line: &str, width: usize) -> String {
+    let mut column = 0;
+    for (i, c) in line.char_indices() {
+        if column >= width || (c != ' ' && c != '\t') {
+            return line[i..].to_string()
+        }
+        let next = advance(column, c);
+        if next > width {
+            return " ".repeat(next - width) + &line[i+1..]
+        }
+        column = next;
+    }
+    "".to_string()
+}
+
+/// The item's continuation lines, dedented, with where each starts in `texts`.
+fn block(texts: &str, column: usize) -> ParsedResult<'_, Vec<(usize, String)>> {
+    let input = texts;
+    let mut lines: Vec<(usize, String)> = vec!();
+    let mut blanks: Vec<usize> = vec!();
+    let (mut texts, mut end) = (texts, texts);
+    while !texts.is_empty() {
+        let (line, rest) = split_first_pattern(texts, "\n");
+        if is_blank(line) {
+            blanks.push(offset_in(input, line));
+        } else if count_indent(line) >= column {
+            lines.extend(blanks.drain(..).map(|offset| (offset, "".to_string())));
+            let text = dedent(line, column);
+            lines.push(((offset_in(input, line) + line.len()).saturating_sub(text.len()), text));
+            end = rest;
+        } else {
+            break;
+        }
+        texts = rest;
+    }
+    ParsedResult::new(lines, end)
+}
+
+fn is_blank_sentence(md: &Md) -> bool {
+    match md {
+        Md::Sentence(Words(words, _)) => words.iter().all(|word| match word {
+            Word::Normal(text) => is_blank(text),
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+fn children(mds: Vec<Md>) -> (Vec<Md>, bool) {
+    let mut blocks: Vec<Md> = vec!();
+    let (mut gap, mut loose) = (false, false);
+    for md in mds {
+        if is_blank_sentence(&md) {
+            gap = !blocks.is_empty();
+            continue;
+        }
+        loose |= gap;
+        gap = false;
+        blocks.push(md);
+    }
+    (blocks, loose)
+}
+
+/// A leading `[ ]` or `[x]` box and the text after it.
+fn task(text: &str) -> Option<(bool, &str)> {
+    let checked = match text.get(..3)? {
+        "[ ]" => false,
+        "[x]" | "[X]" => true,
+        _ => return None,
     };
-    let item = Item(words, i);
-    Some(ParsedResult::new(item, rest))
+    let rest = &text[3..];
+    if !rest.starts_with([' ', '\t']) { return None }
+    Some((checked, rest.trim_start_matches([' ', '\t'])))
 }
 
-fn items(mut texts: &str, tab_num: usize) -> ParsedResult<Items> {
+fn item<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, (Item, bool)>> {
+    let (line, rest) = split_first_pattern(texts, "\n");
+    let marker = marker(line)?;
+    let column = child_column(&marker, opts);
+    let block = block(rest, column);
+    let (checked, text) = match task(marker.text).filter(|_| opts.task_lists) {
+        Some((checked, text)) => (Some(checked), text),
+        None => (None, marker.text),
+    };
+    let mut lines = vec!((offset_in(texts, text), text));
+    let offset = offset_in(texts, rest);
+    lines.extend(block.token.iter().map(|(start, line)| (offset + start, line.as_str())));
+    let (mds, loose) = children(parse_mapped(&lines, opts).ok()?);
+    let start = offset_in(texts, line.trim_start_matches([' ', '\t']));
+    let end = texts[..(texts.len() - block.rest.len())].trim_end().len();
+    Some(ParsedResult::new((Item(mds, checked, Span::new(start, end)), loose), block.rest))
+}
+
+fn skip_blank(mut texts: &str) -> &str {
+    while !texts.is_empty() {
+        let (line, rest) = split_first_pattern(texts, "\n");
+        if !is_blank(line) { break }
+        texts = rest;
+    }
+    texts
+}
+
+fn items<'a>(input: &'a str, opts: &ParserOptions) -> ParsedResult<'a, List> {
     let mut items: Vec<Item> = vec!();
-    while let Some(i) = item(texts, tab_num) {
-        if count_tab(texts) < tab_num { break; }
-        items.push(i.token);
+    let mut loose = false;
+    let mut texts = input;
+    while let Some(i) = item(texts, opts) {
+        let (mut item, item_loose) = i.token;
+        let offset = input.len() - texts.len();
+        shift(&mut item.0, offset);
+        item.2.map(&|start| start + offset);
+        items.push(item);
+        loose |= item_loose;
         texts = i.rest;
+
+        let rest = skip_blank(texts);
+        let (line, _) = split_first_pattern(rest, "\n");
+        if rest != texts && marker(line).is_some() {
+            loose = true;
+            texts = rest;
+        }
     }
-    let items = Items(items);
-    ParsedResult::new(items, texts)
+    let list = List { items: Items(items), loose, span: Span::default() };
+    ParsedResult::new(list, texts)
 }
 
-pub fn list(texts: &str) -> Option<ParsedResult<Md>> {
-    let l = items(texts, 0);
-    match l.token {
-        Items(item) if item.is_empty() => None,
-        _ => Some(ParsedResult{token: Md::List(l.token), rest: l.rest})
-    }
+pub fn list<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    let l = items(texts, opts);
+    if l.token.items.0.is_empty() { return None }
+    Some(ParsedResult{token: Md::List(l.token), rest: l.rest})
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::{words,items,normal_word};
+    use crate::{words,items,record,normal_word};
+
+    fn item<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, (Item, bool)>> {
+        super::item(texts, opts).without_spans()
+    }
+
+    fn items<'a>(texts: &'a str, opts: &ParserOptions) -> ParsedResult<'a, List> {
+        super::items(texts, opts).without_spans()
+    }
+
+    fn list<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::list(texts, opts).without_spans()
+    }
+
+    fn leaf(text: &str) -> Item {
+        Item::new(vec!(Md::Sentence(words!(normal_word!(text)))), None)
+    }
+
+    fn node(text: &str, items: Items) -> Item {
+        let list = List { items, loose: false, span: Span::default() };
+        Item::new(vec!(Md::Sentence(words!(normal_word!(text))), Md::List(list)), None)
+    }
+
+    fn tight(items: Items) -> List {
+        List { items, loose: false, span: Span::default() }
+    }
 
     #[test]
     fn test_item() {
-        let words = words!(normal_word!("Hello World!"));
-        let token = Item(words, items!());
+        let opts = ParserOptions::default();
+        let token = (leaf("Hello World!"), false);
         let rest = "";
-        assert_eq!(item(&"- Hello World!\n", 0), Some(ParsedResult{token, rest}));
+        assert_eq!(item("- Hello World!\n", &opts), Some(ParsedResult{token, rest}));
 
-        let words = words!(normal_word!("Hello World!"));
-        let token = Item(words, items!());
+        let token = (leaf("Hello World!"), false);
         let rest = "";
-        assert_eq!(item(&"- Hello World!", 0), Some(ParsedResult{token, rest}));
-        assert_eq!(item(&"Hello World!", 0), None);
-        assert_eq!(item(&"-Hello World!", 0), None);
+        assert_eq!(item("- Hello World!", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(item("Hello World!", &opts), None);
+        assert_eq!(item("-Hello World!", &opts), None);
+    }
+
+    #[test]
+    fn test_task() {
+        assert_eq!(task("[ ] todo"), Some((false, "todo")));
+        assert_eq!(task("[X]\tdone"), Some((true, "done")));
+        assert_eq!(task("[x]done"), None);
+        assert_eq!(task("[-] todo"), None);
+
+        let opts = ParserOptions::gfm();
+        let token = (Item::new(vec!(Md::Sentence(words!(normal_word!("done")))), Some(true)), false);
+        assert_eq!(item("- [x] done", &opts), Some(ParsedResult{token, rest: ""}));
+        assert_eq!(item("- [x] done", &ParserOptions::default()).map(|i| i.token.0.1), Some(None));
     }
 
     #[test]
     fn test_items() {
-        let words = words!(normal_word!("Hello"));
-        let item0 = Item(words, items!());
-        let words = words!(normal_word!("World"));
-        let item1 = Item(words, items!());
-        let words = words!(normal_word!("Rust"));
-        let item2 = Item(words, items!());
-        let token = items!(item0, item1, item2);
+        let opts = ParserOptions::default();
+        let token = tight(items!(leaf("Hello"), leaf("World"), leaf("Rust")));
         let rest = "";
-        assert_eq!(items(&"- Hello\n- World\n- Rust", 0), ParsedResult{token, rest});
-        assert_eq!(items(&"Rust", 0), ParsedResult{token: items!(), rest: "Rust"});
+        assert_eq!(items("- Hello\n- World\n- Rust", &opts), ParsedResult{token, rest});
+        assert_eq!(items("Rust", &opts), ParsedResult{token: tight(items!()), rest: "Rust"});
     }
 
     #[test]
     fn test_nest_items() {
-        let words = words!(normal_word!("World"));
-        let children = items!(Item(words, items!()));
-        let words = words!(normal_word!("Hello"));
-        let token = items!(Item(words, children));
+        let opts = ParserOptions::default();
+        let token = tight(items!(node("Hello", items!(leaf("World")))));
         let rest = "";
-        assert_eq!(items(&"- Hello\n  - World", 0), ParsedResult{token, rest});
+        assert_eq!(items("- Hello\n  - World", &opts), ParsedResult{token, rest});
 
-        let world = words!(normal_word!("World"));
-        let item0 = Item(world, items!());
-        let world = words!(normal_word!("End"));
-        let item1 = Item(world, items!());
-        let children = items!(item0, item1);
-        let words = words!(normal_word!("Hello"));
-        let token = items!(Item(words, children));
+        let children = items!(leaf("World"), leaf("End"));
+        let token = tight(items!(node("Hello", children)));
         let rest = "";
-        assert_eq!(items(&"- Hello\n  - World\n  - End", 0), ParsedResult{token, rest});
+        assert_eq!(items("- Hello\n  - World\n  - End", &opts), ParsedResult{token, rest});
 
-        let words = words!(normal_word!("World"));
-        let children = items!(Item(words, items!()));
-        let words = words!(normal_word!("Hello"));
-        let item0 = Item(words, children);
-        let end = words!(normal_word!("End"));
-        let item1 = Item(end, items!());
-        let token = items!(item0, item1);
+        let item0 = node("Hello", items!(leaf("World")));
+        let token = tight(items!(item0, leaf("End")));
         let rest = "";
-        assert_eq!(items(&"- Hello\n  - World\n- End", 0), ParsedResult{token, rest});
+        assert_eq!(items("- Hello\n  - World\n- End", &opts), ParsedResult{token, rest});
 
-        let words = words!(normal_word!("World"));
-        let item0 = Item(words, items!());
-        let words = words!(normal_word!("End"));
-        let item1 = Item(words, items!());
-        let children = items!(item0, item1);
+        let children = items!(leaf("World"), leaf("End"));
+        let token = tight(items!(node("Hello", children), leaf("Reboot")));
+        let rest = "";
+        assert_eq!(items("- Hello\n  - World\n  - End\n- Reboot", &opts), ParsedResult{token, rest});
 
-        let words = words!(normal_word!("Hello"));
-        let item0 = Item(words, children);
-        let words = words!(normal_word!("Reboot"));
-        let item1 = Item(words, items!());
-        let token = items!(item0, item1);
+        let children = items!(node("World", items!(leaf("End"))));
+        let token = tight(items!(node("Hello", children), leaf("Reboot")));
         let rest = "";
-        assert_eq!(items(&"- Hello\n  - World\n  - End\n- Reboot", 0), ParsedResult{token, rest});
+        assert_eq!(items("- Hello\n  - World\n    - End\n- Reboot", &opts), ParsedResult{token, rest});
+    }
 
+    #[test]
+    fn test_indent() {
+        assert_eq!(count_indent("  hello"), 2);
+        assert_eq!(count_indent("hello"), 0);
+        assert_eq!(count_indent("     hello"), 5);
+        assert_eq!(count_indent("\thello"), 4);
+        assert_eq!(count_indent("  \thello"), 4);
+        assert_eq!(count_indent("\t  hello"), 6);
+    }
 
-        let words = words!(normal_word!("End"));
-        let children = items!(Item(words, items!()));
+    #[test]
+    fn test_dedent() {
+        assert_eq!(dedent("    hello", 2), "  hello");
+        assert_eq!(dedent("\thello", 4), "hello");
+        assert_eq!(dedent("\thello", 2), "  hello");
+        assert_eq!(dedent(" hello", 2), "hello");
+    }
 
-        let words = words!(normal_word!("World"));
-        let children = items!(Item(words, children));
+    #[test]
+    fn test_marker() {
+        let token = Marker{ column: 0, content: 2, text: "Hello" };
+        assert_eq!(marker("- Hello"), Some(token));
+        let token = Marker{ column: 2, content: 6, text: "Hello" };
+        assert_eq!(marker("  -   Hello"), Some(token));
+        let token = Marker{ column: 0, content: 4, text: "Hello" };
+        assert_eq!(marker("-\tHello"), Some(token));
+        let token = Marker{ column: 0, content: 2, text: "Hello" };
+        assert_eq!(marker("-      Hello"), Some(token));
+        assert_eq!(marker("-Hello"), None);
+    }
 
-        let words = words!(normal_word!("Hello"));
-        let item0 = Item(words, children);
+    #[test]
+    fn test_content_column() {
+        let opts = ParserOptions::default();
+        let token = tight(items!(node("Hello", items!(leaf("World")))));
+        let rest = "";
+        assert_eq!(items("- Hello\n\t- World", &opts), ParsedResult{token, rest});
 
-        let words = words!(normal_word!("Reboot"));
-        let item1 = Item(words, items!());
+        let token = tight(items!(node("Hello", items!(leaf("World")))));
+        let rest = "";
+        assert_eq!(items("-   Hello\n    - World", &opts), ParsedResult{token, rest});
 
-        let token = items!(item0, item1);
+        let token = tight(items!(node("Hello", items!(leaf("World")))));
         let rest = "";
-        assert_eq!(items(&"- Hello\n  - World\n    - End\n- Reboot", 0), ParsedResult{token, rest});
+        assert_eq!(items("- Hello\n   - World", &opts), ParsedResult{token, rest});
+
+        let token = tight(items!(leaf("Hello"), leaf("World")));
+        let rest = "";
+        assert_eq!(items("- Hello\n - World", &opts), ParsedResult{token, rest});
+    }
+
+    #[test]
+    fn test_fixed_indent() {
+        let opts = ParserOptions { list_indent: ListIndent::FourSpaces, ..ParserOptions::default() };
+        let token = tight(items!(leaf("Hello"), leaf("World")));
+        let rest = "";
+        assert_eq!(items("- Hello\n  - World", &opts), ParsedResult{token, rest});
+
+        let children = items!(node("World", items!(leaf("End"))));
+        let token = tight(items!(node("Hello", children)));
+        let rest = "";
+        assert_eq!(items("- Hello\n    - World\n\t\t- End", &opts), ParsedResult{token, rest});
+
+        let opts = ParserOptions { list_indent: ListIndent::TwoSpaces, ..ParserOptions::default() };
+        let token = tight(items!(node("Hello", items!(leaf("World")))));
+        let rest = "";
+        assert_eq!(items("-   Hello\n  - World", &opts), ParsedResult{token, rest});
+    }
+
+    #[test]
+    fn test_blocks() {
+        let opts = ParserOptions::default();
+        let hello = Md::Sentence(words!(normal_word!("Hello")));
+        let world = Md::Sentence(words!(normal_word!("World")));
+        let token = tight(items!(Item::new(vec!(hello, world), None)));
+        let rest = "End";
+        assert_eq!(items("- Hello\n  World\nEnd", &opts), ParsedResult{token, rest});
+
+        let hello = Md::Sentence(words!(normal_word!("Hello")));
+        let header = record!(words!(normal_word!("A")));
+        let records = vec!(record!(words!(normal_word!("a"))));
+        let table = Md::Table(Box::new(Table{header, align: vec!(Align::Left), records, span: Span::default()}));
+        let token = tight(items!(Item::new(vec!(hello, table), None)));
+        let rest = "";
+        assert_eq!(items("- Hello\n  | A |\n  |---|\n  | a |", &opts), ParsedResult{token, rest});
     }
 
     #[test]
-    fn test_tab() {
-        assert_eq!(count_tab("  hello"), 1);
-        assert_eq!(count_tab("hello"), 0);
-        assert_eq!(count_tab("     hello"), 2);
+    fn test_loose() {
+        let opts = ParserOptions::default();
+        let token = List { items: items!(leaf("Hello"), leaf("World")), loose: true, span: Span::default() };
+        let rest = "";
+        assert_eq!(items("- Hello\n\n- World", &opts), ParsedResult{token, rest});
+
+        let hello = Md::Sentence(words!(normal_word!("Hello")));
+        let world = Md::Sentence(words!(normal_word!("World")));
+        let token = List { items: items!(Item::new(vec!(hello, world), None)), loose: true, span: Span::default() };
+        let rest = "\nEnd";
+        assert_eq!(items("- Hello\n\n  World\n\nEnd", &opts), ParsedResult{token, rest});
+
+        let token = tight(items!(leaf("Hello")));
+        let rest = "\nWorld";
+        assert_eq!(items("- Hello\n\nWorld", &opts), ParsedResult{token, rest});
+
+        let children = List { items: items!(leaf("World"), leaf("End")), loose: true, span: Span::default() };
+        let hello = Md::Sentence(words!(normal_word!("Hello")));
+        let token = tight(items!(Item::new(vec!(hello, Md::List(children)), None)));
+        let rest = "";
+        assert_eq!(items("- Hello\n  - World\n\n  - End", &opts), ParsedResult{token, rest});
     }
 
     #[test]
     fn test_list() {
-        let words = words!(normal_word!("World"));
-        let children = items!(Item(words, items!()));
-        let words = words!(normal_word!("Hello"));
-        let item = Item(words, children);
-        let token = Md::List(items!(item));
+        let opts = ParserOptions::default();
+        let token = Md::List(tight(items!(node("Hello", items!(leaf("World"))))));
         let rest = "";
-        assert_eq!(list(&"- Hello\n  - World"), Some(ParsedResult{token, rest}));
+        assert_eq!(list("- Hello\n  - World", &opts), Some(ParsedResult{token, rest}));
     }
-}
\ No newline at end of file
+}
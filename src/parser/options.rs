@@ -0,0 +1,153 @@
+use std::fmt;
+use std::sync::Arc;
+use crate::parser::block::BlockParser;
+use crate::parser::inline::InlineParser;
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum ListIndent {
+    /// Nest by the column where the item's text starts, as CommonMark does.
+    ContentColumn,
+    /// Every two columns of indentation is one level.
+    TwoSpaces,
+    /// Every four columns of indentation is one level.
+    FourSpaces,
+}
+
+/// Which syntaxes the parser accepts. Start from a preset and switch
+/// single extensions on or off:
+///
+/// ```
+/// use app::parser::parser::ParserOptions;
+///
+/// let opts = ParserOptions { footnotes: false, ..ParserOptions::gfm() };
+/// assert!(opts.tables && !opts.footnotes);
+/// ```
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParserOptions {
+    pub list_indent: ListIndent,
+    /// Pipe tables.
+    pub tables: bool,
+    /// `~~text~~` as `<s>`.
+    pub strikethrough: bool,
+    /// `__text__` as `<u>`; otherwise it is strong, as in CommonMark.
+    pub underline: bool,
+    /// `- [ ]` and `- [x]` list items as checkboxes.
+    pub task_lists: bool,
+    /// `[^label]` references and `[^label]: text` definitions.
+    pub footnotes: bool,
+    /// `:::name` containers and `???` or `<details>` folds.
+    pub containers: bool,
+    /// `> [!NOTE]` quotes as admonitions.
+    pub admonitions: bool,
+    /// `{#id .class key=value}` after headings, code fences, links and
+    /// images, and `[text]{.class}` spans.
+    pub attributes: bool,
+    /// A `[TOC]` line as the table of contents.
+    pub toc: bool,
+    /// `==text==` as `<mark>`.
+    pub highlight: bool,
+    /// `^text^` as `<sup>`.
+    pub superscript: bool,
+    /// `~text~` as `<sub>`, alongside `~~` strike-through.
+    pub subscript: bool,
+    /// `:rocket:` shortcodes as emoji.
+    pub emoji: bool,
+    /// `[[Page Name]]` and `[[Page Name|label]]` links.
+    pub wiki_links: bool,
+    /// `*[HTML]: Hyper Text Markup Language` definitions.
+    pub abbreviations: bool,
+    /// Curly quotes, `--` and `---` as en and em dashes, and `...` as an ellipsis.
+    pub smart_punctuation: bool,
+    /// Pandoc-style `[@key, p. 4]` and `@key` citations.
+    pub citations: bool,
+    /// Syntaxes added or disabled through a `Parser`.
+    pub extensions: Extensions,
+}
+
+impl ParserOptions {
+    /// Only CommonMark syntax.
+    pub fn commonmark() -> ParserOptions {
+        ParserOptions {
+            list_indent: ListIndent::ContentColumn,
+            tables: false,
+            strikethrough: false,
+            underline: false,
+            task_lists: false,
+            footnotes: false,
+            containers: false,
+            admonitions: false,
+            attributes: false,
+            toc: false,
+            highlight: false,
+            superscript: false,
+            subscript: false,
+            emoji: false,
+            wiki_links: false,
+            abbreviations: false,
+            smart_punctuation: false,
+            citations: false,
+            extensions: Extensions::default(),
+        }
+    }
+
+    /// GitHub Flavored Markdown, with the footnotes GitHub also renders.
+    pub fn gfm() -> ParserOptions {
+        ParserOptions {
+            tables: true,
+            strikethrough: true,
+            task_lists: true,
+            footnotes: true,
+            ..ParserOptions::commonmark()
+        }
+    }
+
+    /// The syntax this crate has always parsed, and the default.
+    pub fn legacy() -> ParserOptions {
+        ParserOptions {
+            tables: true,
+            strikethrough: true,
+            underline: true,
+            containers: true,
+            admonitions: true,
+            attributes: true,
+            toc: true,
+            ..ParserOptions::commonmark()
+        }
+    }
+}
+
+impl Default for ParserOptions {
+    fn default() -> Self {
+        ParserOptions::legacy()
+    }
+}
+
+/// The syntaxes a `Parser` adds, and the built-in blocks it skips.
+/// Options compare equal when they share the same registered parsers.
+#[derive(Clone, Default)]
+pub struct Extensions {
+    pub(crate) blocks: Vec<Arc<dyn BlockParser>>,
+    pub(crate) disabled: Vec<String>,
+    pub(crate) inlines: Vec<Arc<dyn InlineParser>>,
+}
+
+impl fmt::Debug for Extensions {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let blocks: Vec<&str> = self.blocks.iter().map(|parser| parser.name()).collect();
+        f.debug_struct("Extensions")
+            .field("blocks", &blocks)
+            .field("disabled", &self.disabled)
+            .field("inlines", &self.inlines.len())
+            .finish()
+    }
+}
+
+impl PartialEq for Extensions {
+    fn eq(&self, other: &Extensions) -> bool {
+        self.blocks.len() == other.blocks.len()
+            && self.blocks.iter().zip(&other.blocks).all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.disabled == other.disabled
+            && self.inlines.len() == other.inlines.len()
+            && self.inlines.iter().zip(&other.inlines).all(|(a, b)| Arc::ptr_eq(a, b))
+    }
+}
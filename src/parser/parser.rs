@@ -1,14 +1,54 @@
-use super::heading::heading;
-use super::sentence::sentence;
-use super::table::table;
-use super::list::list;
+use super::slug::heading_ids;
+use super::emoji::shortcodes;
+use super::abbreviation::abbreviations;
+use super::block::block_parsers;
+pub use super::options::*;
+pub use super::block::{BlockParser, Parser};
+pub use super::inline::{InlineParser, Delimited};
+pub use super::error::*;
+pub use super::span::*;
+use super::span::shift;
 
 #[derive(Debug, PartialEq)]
 pub enum Md {
-    Heading(usize, Words),
+    Heading(Heading),
     Sentence(Words),
     Table(Box<Table>),
-    List(Items),
+    List(List),
+    Toc(Span),
+    Code(CodeBlock),
+    Quote(Vec<Md>, Span),
+    Admonition {
+        kind: AdmonitionKind,
+        title: Option<Words>,
+        body: Vec<Md>,
+        span: Span,
+    },
+    Abbreviation { abbr: String, title: String, span: Span },
+    /// A `:::name` container, or a block produced by a registered
+    /// `BlockParser`.
+    Container {
+        name: String,
+        attrs: Attributes,
+        body: Vec<Md>,
+        span: Span,
+    },
+    Details {
+        summary: Words,
+        open: bool,
+        body: Vec<Md>,
+        span: Span,
+    },
+    Footnote { label: String, body: Vec<Md>, span: Span },
+}
+
+#[derive(Debug, PartialEq, Clone, Copy)]
+pub enum AdmonitionKind {
+    Note,
+    Tip,
+    Important,
+    Warning,
+    Caution,
 }
 
 #[derive(Debug, PartialEq)]
@@ -18,6 +58,44 @@ pub enum Word {
     Bold(Words),
     StrikeThough(Words),
     Underline(Words),
+    Highlight(Words),
+    Superscript(Words),
+    Subscript(Words),
+    Code(String),
+    Emoji { shortcode: String, char: String },
+    WikiLink { page: String, label: Option<String> },
+    Link { words: Words, url: String, title: Option<String>, attrs: Attributes },
+    Image { alt: String, url: String, title: Option<String>, attrs: Attributes },
+    Span { words: Words, attrs: Attributes },
+    Abbreviation { abbr: String, title: String },
+    /// `[^label]`, numbered by `Md::Footnote` definitions when rendered.
+    Footnote { label: String },
+    /// `[@a, p. 4; -@b]`, or `@a` written in the running text when `in_text` is set.
+    Citation { cites: Vec<Cite>, in_text: bool },
+    /// Words produced by a registered `InlineParser`, such as `Delimited`.
+    Extension { name: String, words: Words },
+}
+
+impl Word {
+    pub fn words(&self) -> Option<&Words> {
+        match self {
+            Word::Italic(words) | Word::Bold(words) | Word::StrikeThough(words) |
+            Word::Underline(words) | Word::Highlight(words) | Word::Superscript(words) |
+            Word::Subscript(words) | Word::Link { words, .. } | Word::Span { words, .. } |
+            Word::Extension { words, .. } => Some(words),
+            _ => None,
+        }
+    }
+
+    pub fn words_mut(&mut self) -> Option<&mut Words> {
+        match self {
+            Word::Italic(words) | Word::Bold(words) | Word::StrikeThough(words) |
+            Word::Underline(words) | Word::Highlight(words) | Word::Superscript(words) |
+            Word::Subscript(words) | Word::Link { words, .. } | Word::Span { words, .. } |
+            Word::Extension { words, .. } => Some(words),
+            _ => None,
+        }
+    }
 }
 #[macro_export]
 macro_rules! normal_word {
@@ -27,7 +105,49 @@ macro_rules! normal_word {
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Item(pub Words, pub Items);
+pub struct Heading {
+    pub level: usize,
+    pub words: Words,
+    /// Explicit `{#id}` or generated slug; never duplicated in `attrs`.
+    pub id: String,
+    pub attrs: Attributes,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct CodeBlock {
+    pub lang: String,
+    pub code: String,
+    pub attrs: Attributes,
+    pub span: Span,
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct Cite {
+    pub key: String,
+    pub locator: Option<String>,
+    /// `-@key`, which cites only the year.
+    pub suppress_author: bool,
+}
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Attributes {
+    pub id: Option<String>,
+    pub classes: Vec<String>,
+    pub pairs: Vec<(String, String)>,
+}
+
+#[derive(Debug, PartialEq)]
+/// An item's blocks, whether it is checked when it is a task, and its span
+/// from the marker on.
+pub struct Item(pub Vec<Md>, pub Option<bool>, pub Span);
+
+impl Item {
+    /// An item without a position, as built by hand.
+    pub fn new(blocks: Vec<Md>, checked: Option<bool>) -> Item {
+        Item(blocks, checked, Span::default())
+    }
+}
 
 #[derive(Debug, PartialEq)]
 pub struct Items(pub Vec<Item>);
@@ -38,53 +158,70 @@ macro_rules! items {
     }};
 
     ( $( $item:expr), *) => {{
-        let mut is = vec!();
-        $(
-            is.push($item);
-        )*
-        Items(is) 
+        Items(vec!($( $item ), *))
     }};
 }
 
 #[derive(Debug, PartialEq)]
-pub struct Words(pub Vec<Word>);
+pub struct List {
+    pub items: Items,
+    pub loose: bool,
+    pub span: Span,
+}
+
+/// Inline words, with the span of each word alongside.
+#[derive(Debug, PartialEq)]
+pub struct Words(pub Vec<Word>, pub Vec<Span>);
 #[macro_export]
 macro_rules! words {
     () => {{
-        panic!("No words!")
+        Words::new(vec!())
     }};
 
     ( $( $word:expr), *) => {{
-        let mut ws = vec!();
-        $(
-            ws.push($word);
-        )*
-        Words(ws) 
+        Words::new(vec!($( $word ), *))
     }}
 }
 
+impl Words {
+    /// Words without positions, as built by hand.
+    pub fn new(words: Vec<Word>) -> Words {
+        let spans = vec!(Span::default(); words.len());
+        Words(words, spans)
+    }
+
+    pub fn span(&self) -> Span {
+        Span::cover(&self.1)
+    }
+}
+
+/// A table row's cells, and the span of the row.
 #[derive(Debug, PartialEq)]
-pub struct Record(pub Vec<Words>);
+pub struct Record(pub Vec<Words>, pub Span);
 #[macro_export]
 macro_rules! record {
     () => {{
-        panic!("No Vec<words>!")
+        Record::new(vec!())
     }};
 
     ( $( $words:expr), *) => {{
-        let mut rd = vec!();
-        $(
-            rd.push($words);
-        )*
-        Record(rd) 
+        Record::new(vec!($( $words ), *))
     }}
 }
 
+impl Record {
+    /// A row without a position, as built by hand.
+    pub fn new(cells: Vec<Words>) -> Record {
+        Record(cells, Span::default())
+    }
+}
+
 #[derive(Debug, PartialEq)]
 pub struct Table {
     pub header: Record,
     pub align: Vec<Align>,
     pub records: Vec<Record>,
+    pub span: Span,
 }
 
 #[derive(Debug, PartialEq)]
@@ -102,7 +239,7 @@ pub struct ParsedResult<'a, T> {
 
 impl<'a, T> ParsedResult<'a, T> {
     pub fn new(token: T, rest: &'a str) -> ParsedResult<'a, T> {
-        ParsedResult { token: token, rest: rest }
+        ParsedResult { token, rest }
     }
 }
 
@@ -117,21 +254,264 @@ pub fn consume<'a>(text: &'a str, pattern: &'a str) -> Option<&'a str> {
     Some(&text[length..])
 }
 
+/// Whether a whitespace-delimited token is a bare URL.
+pub fn is_url(token: &str) -> bool {
+    token.contains("://") || token.starts_with("www.")
+}
+
 pub fn split_first_pattern<'a>(texts: &'a str, pattern: &str) -> (&'a str, &'a str) {
     if let Some(n) = texts.find(pattern) {
         let len = pattern.len();
         (&texts[..n], &texts[(n+len)..])
     } else {
-        (texts, "")
+        (texts, &texts[texts.len()..])
     }
 }
 
-pub fn parse(mut text: &str) -> Vec<Md> {
-    let parsers = vec!(table, list, heading, sentence);
+pub fn parse(text: &str) -> Result<Vec<Md>, ParseError> {
+    parse_with(text, &ParserOptions::default())
+}
+
+impl Md {
+    /// Nested block sequences, such as list item bodies and quote bodies.
+    pub fn children(&self) -> Vec<&Vec<Md>> {
+        match self {
+            Md::List(list) => list.items.0.iter().map(|item| &item.0).collect(),
+            Md::Quote(body, _) | Md::Admonition { body, .. } | Md::Container { body, .. } |
+            Md::Details { body, .. } | Md::Footnote { body, .. } => vec!(body),
+            _ => vec!(),
+        }
+    }
+
+    pub fn span(&self) -> Span {
+        match self {
+            Md::Sentence(words) => words.span(),
+            Md::Heading(Heading { span, .. }) | Md::Code(CodeBlock { span, .. }) | Md::List(List { span, .. }) |
+            Md::Toc(span) | Md::Quote(_, span) | Md::Admonition { span, .. } | Md::Abbreviation { span, .. } |
+            Md::Container { span, .. } | Md::Details { span, .. } | Md::Footnote { span, .. } => *span,
+            Md::Table(table) => table.span,
+        }
+    }
+
+    /// The span of the block itself; a sentence's is that of its words.
+    fn span_mut(&mut self) -> Option<&mut Span> {
+        match self {
+            Md::Sentence(_) => None,
+            Md::Heading(Heading { span, .. }) | Md::Code(CodeBlock { span, .. }) | Md::List(List { span, .. }) |
+            Md::Toc(span) | Md::Quote(_, span) | Md::Admonition { span, .. } | Md::Abbreviation { span, .. } |
+            Md::Container { span, .. } | Md::Details { span, .. } | Md::Footnote { span, .. } => Some(span),
+            Md::Table(table) => Some(&mut table.span),
+        }
+    }
+
+    pub fn children_mut(&mut self) -> Vec<&mut Vec<Md>> {
+        match self {
+            Md::List(list) => list.items.0.iter_mut().map(|item| &mut item.0).collect(),
+            Md::Quote(body, _) | Md::Admonition { body, .. } | Md::Container { body, .. } |
+            Md::Details { body, .. } | Md::Footnote { body, .. } => vec!(body),
+            _ => vec!(),
+        }
+    }
+}
+
+pub fn walk(mds: &[Md], f: &mut dyn FnMut(&Md)) {
+    for md in mds.iter() {
+        f(md);
+        for body in md.children() {
+            walk(body, f);
+        }
+    }
+}
+
+pub fn walk_mut(mds: &mut [Md], f: &mut dyn FnMut(&mut Md)) {
+    for md in mds.iter_mut() {
+        f(md);
+        for body in md.children_mut() {
+            walk_mut(body, f);
+        }
+    }
+}
+
+/// Visits the top-level block sequence and every nested one, so passes can
+/// insert or remove blocks.
+pub fn walk_blocks_mut(mds: &mut Vec<Md>, f: &mut dyn FnMut(&mut Vec<Md>)) {
+    f(mds);
+    for md in mds.iter_mut() {
+        for body in md.children_mut() {
+            walk_blocks_mut(body, f);
+        }
+    }
+}
+
+fn words_ref(words: &Words, f: &mut dyn FnMut(&Words)) {
+    f(words);
+    for word in words.0.iter() {
+        if let Some(inner) = word.words() {
+            words_ref(inner, f);
+        }
+    }
+}
+
+pub fn walk_words(mds: &[Md], f: &mut dyn FnMut(&Words)) {
+    walk(mds, &mut |md| match md {
+        Md::Heading(heading) => words_ref(&heading.words, f),
+        Md::Sentence(words) => words_ref(words, f),
+        Md::Table(table) => {
+            let records = std::iter::once(&table.header).chain(table.records.iter());
+            for cell in records.flat_map(|record| record.0.iter()) {
+                words_ref(cell, f);
+            }
+        },
+        Md::Admonition { title: Some(words), .. } => words_ref(words, f),
+        Md::Details { summary, .. } => words_ref(summary, f),
+        _ => {},
+    });
+}
+
+fn words_mut(words: &mut Words, f: &mut dyn FnMut(&mut Words)) {
+    f(words);
+    for word in words.0.iter_mut() {
+        if let Some(inner) = word.words_mut() {
+            words_mut(inner, f);
+        }
+    }
+}
+
+/// Visits only the words held directly by blocks, leaving nested words such
+/// as link text to `f`.
+pub fn walk_block_words_mut(mds: &mut [Md], f: &mut dyn FnMut(&mut Words)) {
+    walk_mut(mds, &mut |md| match md {
+        Md::Heading(heading) => f(&mut heading.words),
+        Md::Sentence(words) => f(words),
+        Md::Table(table) => {
+            let records = std::iter::once(&mut table.header).chain(table.records.iter_mut());
+            for cell in records.flat_map(|record| record.0.iter_mut()) {
+                f(cell);
+            }
+        },
+        Md::Admonition { title: Some(words), .. } => f(words),
+        Md::Details { summary, .. } => f(summary),
+        _ => {},
+    });
+}
+
+pub fn walk_words_mut(mds: &mut [Md], f: &mut dyn FnMut(&mut Words)) {
+    walk_block_words_mut(mds, &mut |words| words_mut(words, f));
+}
+
+pub(crate) fn words_spans_mut(words: &mut Words, f: &mut dyn FnMut(&mut Span)) {
+    words_mut(words, &mut |words| words.1.iter_mut().for_each(&mut *f));
+}
+
+/// Visits the span of every block, list item, table row and word.
+pub fn walk_spans_mut(mds: &mut [Md], f: &mut dyn FnMut(&mut Span)) {
+    walk_mut(mds, &mut |md| {
+        if let Some(span) = md.span_mut() { f(span) }
+        match md {
+            Md::List(list) => list.items.0.iter_mut().for_each(|item| f(&mut item.2)),
+            Md::Table(table) => {
+                let records = std::iter::once(&mut table.header).chain(table.records.iter_mut());
+                records.for_each(|record| f(&mut record.1));
+            },
+            _ => {},
+        }
+    });
+    walk_block_words_mut(mds, &mut |words| words_spans_mut(words, f));
+}
+
+/// Words split out of a text, each with its offset into the text.
+pub(crate) type Pieces = Vec<(usize, Word)>;
+
+/// Replaces each `Word::Normal` that `split` breaks up with its pieces,
+/// given as their offsets into the text. Each piece takes the part of the
+/// word's span it came from, and spans inside a piece are taken as relative
+/// to the text.
+pub(crate) fn split_normal(words: &mut Words, split: &mut dyn FnMut(&str) -> Option<Pieces>) {
+    let tokens = std::mem::take(&mut words.0);
+    let mut spans = std::mem::take(&mut words.1);
+    spans.resize(tokens.len(), Span::default());
+    for (word, span) in tokens.into_iter().zip(spans) {
+        let Word::Normal(text) = &word else {
+            words.0.push(word);
+            words.1.push(span);
+            continue
+        };
+        let Some(pieces) = split(text) else {
+            words.0.push(word);
+            words.1.push(span);
+            continue
+        };
+        let ends: Vec<usize> = pieces.iter().skip(1).map(|(start, _)| *start).chain([text.len()]).collect();
+        for ((start, mut piece), end) in pieces.into_iter().zip(ends) {
+            let piece_span = span.slice(text, start..end);
+            if let Some(inner) = piece.words_mut() {
+                words_spans_mut(inner, &mut |inner| *inner = span.slice(text, inner.range()));
+            }
+            words.0.push(piece);
+            words.1.push(piece_span);
+        }
+    }
+}
+
+pub fn parse_with(text: &str, opts: &ParserOptions) -> Result<Vec<Md>, ParseError> {
+    let mut mds = parse_blocks(text, opts)?;
+    document_passes(&mut mds, opts);
+    locate(&mut mds, text);
+    Ok(mds)
+}
+
+/// The document-wide passes `parse_with` runs after block parsing.
+pub fn document_passes(mds: &mut Vec<Md>, opts: &ParserOptions) {
+    if opts.emoji { shortcodes(mds) }
+    if opts.abbreviations { abbreviations(mds) }
+    heading_ids(mds);
+}
+
+pub fn parse_blocks(source: &str, opts: &ParserOptions) -> Result<Vec<Md>, ParseError> {
+    let parsers = block_parsers(opts);
     let mut md: Vec<Md> = vec!();
-    while let Some(ret) = parsers.iter().find_map(|f| f(text)) {
-        md.push(ret.token);
+    let mut text = source;
+    while !text.is_empty() {
+        let Some(ret) = parsers.iter().find_map(|parser| parser.parse(text, opts)) else {
+            return Err(ParseError::at(source, text, ParseErrorKind::UnexpectedInput))
+        };
+        if ret.rest.len() >= text.len() {
+            return Err(ParseError::at(source, text, ParseErrorKind::NoProgress))
+        }
+        let mut token = ret.token;
+        if let Some(span) = token.span_mut() {
+            let consumed = &text[..(text.len() - ret.rest.len())];
+            *span = Span::new(0, consumed.trim_end_matches('\n').len());
+        }
+        shift(std::slice::from_mut(&mut token), source.len() - text.len());
+        md.push(token);
         text = ret.rest;
     }
-    md
+    Ok(md)
+}
+
+/// Parses nested blocks from `lines`, each a slice of `text`, keeping
+/// their spans relative to `text`.
+pub fn parse_lines(text: &str, lines: &[&str], opts: &ParserOptions) -> Result<Vec<Md>, ParseError> {
+    let lines: Vec<(usize, &str)> = lines.iter().map(|line| (offset_in(text, line), *line)).collect();
+    parse_mapped(&lines, opts)
+}
+
+/// Parses nested blocks from lines paired with where each starts in the
+/// enclosing text, mapping spans back to that text.
+pub fn parse_mapped(lines: &[(usize, &str)], opts: &ParserOptions) -> Result<Vec<Md>, ParseError> {
+    let joined: Vec<&str> = lines.iter().map(|(_, line)| *line).collect();
+    let mut mds = parse_blocks(&joined.join("\n"), opts)?;
+    let mut starts: Vec<(usize, usize)> = vec!();
+    let mut start = 0;
+    for (offset, line) in lines {
+        starts.push((start, *offset));
+        start += line.len() + 1;
+    }
+    let map = |offset: usize| {
+        let line = starts.partition_point(|(start, _)| *start <= offset).saturating_sub(1);
+        starts.get(line).map_or(offset, |(start, source)| source + (offset - start))
+    };
+    walk_spans_mut(&mut mds, &mut |span| span.map(&map));
+    Ok(mds)
 }
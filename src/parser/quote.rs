@@ -0,0 +1,109 @@
+use crate::parser::parser::*;
+use super::sentence::words;
+
+fn quote_line(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    if indent > 3 { return None }
+    let text = consume(&line[indent..], ">")?;
+    Some(consume(text, " ").unwrap_or(text))
+}
+
+fn quote_lines(mut texts: &str) -> ParsedResult<'_, Vec<&str>> {
+    let mut lines: Vec<&str> = vec!();
+    while !texts.is_empty() {
+        let (line, rest) = split_first_pattern(texts, "\n");
+        let Some(line) = quote_line(line) else { break };
+        lines.push(line);
+        texts = rest;
+    }
+    ParsedResult::new(lines, texts)
+}
+
+fn admonition_kind(text: &str) -> Option<AdmonitionKind> {
+    match text.to_uppercase().as_str() {
+        "NOTE" => Some(AdmonitionKind::Note),
+        "TIP" => Some(AdmonitionKind::Tip),
+        "IMPORTANT" => Some(AdmonitionKind::Important),
+        "WARNING" => Some(AdmonitionKind::Warning),
+        "CAUTION" => Some(AdmonitionKind::Caution),
+        _ => None,
+    }
+}
+
+fn admonition(line: &str, opts: &ParserOptions) -> Option<(AdmonitionKind, Option<Words>)> {
+    if !opts.admonitions { return None }
+    let text = consume(line.trim(), "[!")?;
+    let (kind, title) = text.split_once(']')?;
+    let kind = admonition_kind(kind)?;
+    let title = title.trim();
+    if title.is_empty() { return Some((kind, None)) }
+    let mut words = words(title, opts)?;
+    shift_words(&mut words, offset_in(line, title));
+    Some((kind, Some(words)))
+}
+
+pub fn quote<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    let lines = quote_lines(texts);
+    let (first, body) = lines.token.split_first()?;
+    let token = match admonition(first, opts) {
+        Some((kind, mut title)) => {
+            if let Some(words) = title.as_mut() {
+                shift_words(words, offset_in(texts, first));
+            }
+            let body = parse_lines(texts, body, opts).ok()?;
+            Md::Admonition { kind, title, body, span: Span::default() }
+        },
+        None => Md::Quote(parse_lines(texts, &lines.token, opts).ok()?, Span::default()),
+    };
+    Some(ParsedResult::new(token, lines.rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn quote<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::quote(texts, opts).without_spans()
+    }
+
+    #[test]
+    fn test_quote_line() {
+        assert_eq!(quote_line("> Hello"), Some("Hello"));
+        assert_eq!(quote_line(">Hello"), Some("Hello"));
+        assert_eq!(quote_line("   >  Hello"), Some(" Hello"));
+        assert_eq!(quote_line("    > Hello"), None);
+        assert_eq!(quote_line("Hello"), None);
+    }
+
+    #[test]
+    fn test_quote() {
+        let opts = ParserOptions::default();
+        let hello = Md::Sentence(words!(normal_word!("Hello")));
+        let world = Md::Sentence(words!(normal_word!("World")));
+        let token = Md::Quote(vec!(hello, world), Span::default());
+        let rest = "End";
+        assert_eq!(quote("> Hello\n> World\nEnd", &opts), Some(ParsedResult{token, rest}));
+        assert_eq!(quote("Hello", &opts), None);
+    }
+
+    #[test]
+    fn test_admonition() {
+        let opts = ParserOptions::default();
+        let body = vec!(Md::Sentence(words!(normal_word!("Be careful"))));
+        let token = Md::Admonition { kind: AdmonitionKind::Warning, title: None, body, span: Span::default() };
+        let rest = "";
+        assert_eq!(quote("> [!WARNING]\n> Be careful", &opts), Some(ParsedResult{token, rest}));
+
+        let title = words!(normal_word!("Heads up"));
+        let body = vec!(Md::Sentence(words!(normal_word!("Read this"))));
+        let token = Md::Admonition { kind: AdmonitionKind::Note, title: Some(title), body, span: Span::default() };
+        let rest = "";
+        assert_eq!(quote("> [!note] Heads up\n> Read this", &opts), Some(ParsedResult{token, rest}));
+
+        let hello = words!(normal_word!("[!UNKNOWN]"));
+        let token = Md::Quote(vec!(Md::Sentence(hello)), Span::default());
+        let rest = "";
+        assert_eq!(quote("> [!UNKNOWN]", &opts), Some(ParsedResult{token, rest}));
+    }
+}
@@ -0,0 +1,233 @@
+use crate::parser::parser::*;
+
+#[derive(Debug, PartialEq, Clone)]
+pub enum Reference {
+    /// `#12` or `org/repo#12`.
+    Issue { repo: Option<String>, number: u64 },
+    /// A short or full SHA, optionally as `org/repo@sha`.
+    Commit { repo: Option<String>, sha: String },
+    /// `@alice`.
+    Mention(String),
+}
+
+impl Reference {
+    fn class(&self) -> &'static str {
+        match self {
+            Reference::Issue { .. } => "issue",
+            Reference::Commit { .. } => "commit",
+            Reference::Mention(_) => "mention",
+        }
+    }
+}
+
+pub trait ReferenceResolver {
+    /// Returns the URL of `reference`, or `None` to leave the text as written.
+    fn resolve(&self, reference: &Reference) -> Option<String>;
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+fn run(text: &str, f: impl Fn(char) -> bool) -> &str {
+    &text[..text.find(|c: char| !f(c)).unwrap_or(text.len())]
+}
+
+fn ends_at_boundary(text: &str, len: usize) -> bool {
+    !text[len..].chars().next().is_some_and(|c| is_word_char(c) || c == '/' || c == '@')
+}
+
+fn issue(text: &str) -> Option<(u64, usize)> {
+    let digits = run(consume(text, "#")?, |c| c.is_ascii_digit());
+    let len = 1 + digits.len();
+    if digits.is_empty() || !ends_at_boundary(text, len) { return None }
+    Some((digits.parse().ok()?, len))
+}
+
+fn sha(text: &str) -> Option<(String, usize)> {
+    let hex = run(text, |c| c.is_ascii_hexdigit());
+    if !(7..=40).contains(&hex.len()) || !ends_at_boundary(text, hex.len()) { return None }
+    if !hex.chars().any(|c| c.is_ascii_digit()) || !hex.chars().any(|c| c.is_ascii_alphabetic()) { return None }
+    Some((hex.to_string(), hex.len()))
+}
+
+fn mention(text: &str) -> Option<(String, usize)> {
+    let name = run(consume(text, "@")?, |c| c.is_ascii_alphanumeric() || c == '-');
+    if name.is_empty() || name.starts_with('-') || !ends_at_boundary(text, 1 + name.len()) { return None }
+    Some((name.to_string(), 1 + name.len()))
+}
+
+fn repo(text: &str) -> Option<(String, usize)> {
+    let owner = run(text, |c| c.is_ascii_alphanumeric() || c == '-');
+    let rest = consume(&text[owner.len()..], "/")?;
+    let name = run(rest, |c| c.is_ascii_alphanumeric() || "-_.".contains(c));
+    if owner.is_empty() || name.is_empty() { return None }
+    let len = owner.len() + 1 + name.len();
+    Some((text[..len].to_string(), len))
+}
+
+fn reference(text: &str) -> Option<(Reference, usize)> {
+    if let Some((repo, len)) = repo(text) {
+        let rest = &text[len..];
+        if let Some((number, n)) = issue(rest) {
+            return Some((Reference::Issue { repo: Some(repo), number }, len + n))
+        }
+        if let Some((sha, n)) = consume(rest, "@").and_then(sha) {
+            return Some((Reference::Commit { repo: Some(repo), sha }, len + 1 + n))
+        }
+    }
+    if let Some((number, len)) = issue(text) {
+        return Some((Reference::Issue { repo: None, number }, len))
+    }
+    if let Some((name, len)) = mention(text) {
+        return Some((Reference::Mention(name), len))
+    }
+    let (sha, len) = sha(text)?;
+    Some((Reference::Commit { repo: None, sha }, len))
+}
+
+fn split(text: &str, resolver: &dyn ReferenceResolver) -> Option<Vec<(usize, Word)>> {
+    let mut tokens: Vec<(usize, Word)> = vec!();
+    let mut plain = 0;
+    let mut index = 0;
+    while index < text.len() {
+        let before = text[..index].chars().next_back();
+        let rest = &text[index..];
+        let c = rest.chars().next().map_or(1, char::len_utf8);
+        if before.is_some_and(|c| is_word_char(c) || "/#@&".contains(c)) {
+            index += c;
+            continue
+        }
+        if before.is_none_or(char::is_whitespace) {
+            let token = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+            if is_url(token) {
+                index += token.len();
+                continue
+            }
+        }
+        let found = reference(rest).and_then(|(reference, len)| Some((resolver.resolve(&reference)?, reference, len)));
+        let Some((url, reference, len)) = found else {
+            index += c;
+            continue
+        };
+        if plain < index {
+            tokens.push((plain, Word::Normal(text[plain..index].to_string())));
+        }
+        let attrs = Attributes { classes: vec!(reference.class().to_string()), ..Attributes::default() };
+        let words = Words(vec!(Word::Normal(rest[..len].to_string())), vec!(Span::new(index, index + len)));
+        tokens.push((index, Word::Link { words, url, title: None, attrs }));
+        index += len;
+        plain = index;
+    }
+    if tokens.is_empty() { return None }
+    if plain < text.len() {
+        tokens.push((plain, Word::Normal(text[plain..].to_string())));
+    }
+    Some(tokens)
+}
+
+fn link_words(words: &mut Words, resolver: &dyn ReferenceResolver) {
+    for word in words.0.iter_mut() {
+        if matches!(word, Word::Link { .. }) { continue }
+        if let Some(inner) = word.words_mut() {
+            link_words(inner, resolver);
+        }
+    }
+    split_normal(words, &mut |text| split(text, resolver));
+}
+
+/// Turns `#12`, `org/repo#12`, `@alice` and commit SHAs into links wherever
+/// `resolver` knows them. Code spans, URLs and existing links are left alone.
+pub fn references(mds: &mut [Md], resolver: &dyn ReferenceResolver) {
+    walk_block_words_mut(mds, &mut |words| link_words(words, resolver));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn split(text: &str, resolver: &dyn ReferenceResolver) -> Option<Vec<(usize, Word)>> {
+        super::split(text, resolver).without_spans()
+    }
+
+    struct GitHub;
+
+    impl ReferenceResolver for GitHub {
+        fn resolve(&self, reference: &Reference) -> Option<String> {
+            let url = match reference {
+                Reference::Issue { repo, number } => format!("{}/issues/{}", repo.as_deref().unwrap_or("org/app"), number),
+                Reference::Commit { repo, sha } => format!("{}/commit/{}", repo.as_deref().unwrap_or("org/app"), sha),
+                Reference::Mention(name) if name == "ghost" => return None,
+                Reference::Mention(name) => name.clone(),
+            };
+            Some(format!("https://github.com/{}", url))
+        }
+    }
+
+    fn link(text: &str, url: &str, class: &str) -> Word {
+        let attrs = Attributes { classes: vec!(class.to_string()), ..Attributes::default() };
+        Word::Link { words: words!(normal_word!(text)), url: format!("https://github.com/{}", url), title: None, attrs }
+    }
+
+    #[test]
+    fn test_reference() {
+        assert_eq!(reference("#12 x"), Some((Reference::Issue { repo: None, number: 12 }, 3)));
+        assert_eq!(reference("org/repo#12."), Some((Reference::Issue { repo: Some("org/repo".to_string()), number: 12 }, 11)));
+        assert_eq!(reference("org/repo@a1b2c3d"), Some((Reference::Commit { repo: Some("org/repo".to_string()), sha: "a1b2c3d".to_string() }, 16)));
+        assert_eq!(reference("@alice,"), Some((Reference::Mention("alice".to_string()), 6)));
+        assert_eq!(reference("deadbeef1"), Some((Reference::Commit { repo: None, sha: "deadbeef1".to_string() }, 9)));
+        assert_eq!(reference("defaced"), None);
+        assert_eq!(reference("1234567"), None);
+        assert_eq!(reference("a1b2c3"), None);
+        assert_eq!(reference("#12a"), None);
+        assert_eq!(reference("#"), None);
+    }
+
+    #[test]
+    fn test_split() {
+        let token = vec!(
+            (0, normal_word!("Fixed ")),
+            (6, link("#12", "org/app/issues/12", "issue")),
+            (9, normal_word!(" (")),
+            (11, link("a1b2c3d", "org/app/commit/a1b2c3d", "commit")),
+            (18, normal_word!(") thanks ")),
+            (27, link("@alice", "alice", "mention")),
+            (33, normal_word!(" and @ghost")),
+        );
+        assert_eq!(split("Fixed #12 (a1b2c3d) thanks @alice and @ghost", &GitHub), Some(token));
+
+        let token = vec!((0, normal_word!("see ")), (4, link("other/lib#3", "other/lib/issues/3", "issue")));
+        assert_eq!(split("see other/lib#3", &GitHub), Some(token));
+    }
+
+    #[test]
+    fn test_split_skips_urls_and_emails() {
+        assert_eq!(split("https://example.com/page#12 and www.a.b/@x", &GitHub), None);
+        assert_eq!(split("mail alice@example.com or a#1", &GitHub), None);
+        assert_eq!(split("entity &#123;", &GitHub), None);
+    }
+
+    #[test]
+    fn test_references() {
+        let existing = Word::Link { words: words!(normal_word!("#1")), url: "/one".to_string(), title: None, attrs: Attributes::default() };
+        let mut mds = vec!(Md::Sentence(words!(
+            Word::Bold(words!(normal_word!("#2"))),
+            normal_word!(" "),
+            Word::Code("#3".to_string()),
+            normal_word!(" "),
+            existing
+        )));
+        references(&mut mds, &GitHub);
+
+        let existing = Word::Link { words: words!(normal_word!("#1")), url: "/one".to_string(), title: None, attrs: Attributes::default() };
+        let expected = vec!(Md::Sentence(words!(
+            Word::Bold(words!(link("#2", "org/app/issues/2", "issue"))),
+            normal_word!(" "),
+            Word::Code("#3".to_string()),
+            normal_word!(" "),
+            existing
+        )));
+        assert_eq!(mds, expected);
+    }
+}
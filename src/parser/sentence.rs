@@ -1,85 +1,198 @@
 use crate::parser::parser::*;
-use crate::{normal_word, words};
+use super::wiki::wiki_link;
+use super::link::{link, image};
+use super::smart::smarten;
+use super::citation::{citation, citation_start};
+use super::inline::inline_word;
+use super::footnote::footnote_ref;
+use crate::normal_word;
 
-fn emphasis<'a>(
+/// Non-empty words between `open` and the first `close` after it.
+pub(crate) fn delimited<'a>(
     text: &'a str,
-    pattern: &'a str,
-    em: &dyn Fn(Words)->Word
+    open: &str,
+    close: &str,
+    em: &dyn Fn(Words)->Word,
+    opts: &ParserOptions
 ) -> Option<ParsedResult<'a, Word>> {
-    let text = consume(text, pattern)?;
-    let index = text.find(pattern)?;
+    let input = text;
+    let text = text.strip_prefix(open)?;
+    let index = text.find(close)?;
     if index == 0 { return  None }
-    let start = pattern.len() + index;
+    let start = close.len() + index;
     let (text, rest) = (&text[..index], &text[start..]);
-    let token = em(words(text));
-    Some(ParsedResult::new(token, rest))
+    let mut words = words(text, opts)?;
+    shift_words(&mut words, offset_in(input, text));
+    Some(ParsedResult::new(em(words), rest))
+}
+
+fn emphasis<'a>(
+    text: &'a str,
+    pattern: &'a str,
+    em: &dyn Fn(Words)->Word,
+    opts: &ParserOptions
+) -> Option<ParsedResult<'a, Word>> {
+    delimited(text, pattern, pattern, em, opts)
+}
+
+fn script<'a>(
+    text: &'a str,
+    pattern: &'a str,
+    em: &dyn Fn(Words)->Word,
+    opts: &ParserOptions
+) -> Option<ParsedResult<'a, Word>> {
+    let inner = consume(text, pattern)?;
+    let index = inner.find(pattern)?;
+    if inner[..index].contains(char::is_whitespace) { return None }
+    emphasis(text, pattern, em, opts)
+}
+
+fn strip_code(code: &str) -> &str {
+    let padded = code.len() >= 2 && code.starts_with(' ') && code.ends_with(' ');
+    if padded && !code.trim().is_empty() {
+        &code[1..code.len()-1]
+    } else {
+        code
+    }
+}
+
+fn code<'a>(text: &'a str, _opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let ticks = text.chars().take_while(|c| c == &'`').count();
+    if ticks == 0 { return None }
+    let (fence, body) = (&text[..ticks], &text[ticks..]);
+    let mut index = 0;
+    while let Some(n) = body[index..].find(fence) {
+        let start = index + n;
+        let run = body[start..].chars().take_while(|c| c == &'`').count();
+        if run == ticks {
+            let token = Word::Code(strip_code(&body[..start]).to_string());
+            return Some(ParsedResult::new(token, &body[(start+ticks)..]))
+        }
+        index = start + run;
+    }
+    Some(ParsedResult::new(normal_word!(fence), body))
+}
+
+fn italic<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    emphasis(text, "*", &Word::Italic, opts)
 }
 
-fn italic(text: &str) -> Option<ParsedResult<Word>> {
-    let em = |token| Word::Italic(token);
-    emphasis(&text, "*", &em)
+fn bold<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    emphasis(text, "**", &Word::Bold, opts)
 }
 
-fn bold(text: &str) -> Option<ParsedResult<Word>> {
-    let em = |token| Word::Bold(token);
-    emphasis(&text, "**", &em)
+fn underline<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let em: fn(Words) -> Word = if opts.underline { Word::Underline } else { Word::Bold };
+    emphasis(text, "__", &em, opts)
 }
 
-fn underline(text: &str) -> Option<ParsedResult<Word>> {
-    let em = |token| Word::Underline(token);
-    emphasis(&text, "__", &em)
+fn strike_though<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.strikethrough { return None }
+    emphasis(text, "~~", &Word::StrikeThough, opts)
 }
 
-fn strike_though(text: &str) -> Option<ParsedResult<Word>> {
-    let em = |token| Word::StrikeThough(token);
-    emphasis(&text, "~~", &em)
+fn highlight<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.highlight { return None }
+    emphasis(text, "==", &Word::Highlight, opts)
 }
 
-fn normal(text: &str) -> Option<ParsedResult<Word>> {
-    let keywords = ["~~", "__", "**", "*"];
+fn superscript<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.superscript { return None }
+    script(text, "^", &Word::Superscript, opts)
+}
+
+fn subscript<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.subscript { return None }
+    script(text, "~", &Word::Subscript, opts)
+}
+
+/// Keywords that open links, and are plain text when no link follows.
+const BRACKETS: [&str; 3] = ["[[", "![", "["];
+
+fn keywords(opts: &ParserOptions) -> Vec<&str> {
+    let mut keywords = vec!("`");
+    if opts.strikethrough { keywords.push("~~") }
+    keywords.extend(["__", "**", "*"]);
+    if opts.highlight { keywords.push("==") }
+    if opts.superscript { keywords.push("^") }
+    if opts.subscript { keywords.push("~") }
+    if opts.wiki_links { keywords.push("[[") }
+    keywords.extend(["![", "["]);
+    keywords.extend(opts.extensions.inlines.iter().flat_map(|parser| parser.triggers()));
+    keywords
+}
+
+fn normal<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let keywords = keywords(opts);
     let matched = keywords.iter().find_map(|p| {
-        let rest = consume(text, p)?;
-        Some(ParsedResult::new(normal_word!(p), &rest))
+        let rest = text.strip_prefix(p)?;
+        Some(ParsedResult::new(normal_word!(p), rest))
     });
     if matched.is_some() {
         return matched
     }
-
-    let index = keywords.iter().filter_map(|p| text.find(p)).min();
-    if let Some(n) = index {
-        let token = &text[..n];
-        let rest = &text[n..];
-        return Some(ParsedResult::new(normal_word!(token), rest))
+    if opts.citations {
+        if let Some(rest) = consume(text, "@") {
+            return Some(ParsedResult::new(normal_word!("@"), rest))
+        }
     }
 
-    let token = normal_word!(text);
-    Some(ParsedResult::new(token,  ""))
+    let cite = if opts.citations { citation_start(text) } else { None };
+    let n = keywords.iter().filter_map(|p| text.find(p)).chain(cite).min().unwrap_or(text.len());
+    let (token, rest) = (&text[..n], &text[n..]);
+    if opts.smart_punctuation {
+        let token = smarten(token, rest.chars().next());
+        return Some(ParsedResult::new(Word::Normal(token), rest))
+    }
+    Some(ParsedResult::new(normal_word!(token), rest))
 }
 
-fn word(text: &str) -> ParsedResult<Word> {
-    let parsers = vec!(underline, strike_though, bold, italic, normal);
-    if let Some(result) = parsers.iter().find_map(|f| f(text)) {
-        result
-    } else {
-        panic!("parse err!")
-    }
+fn word<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    let parsers = [
+        code, inline_word, footnote_ref, wiki_link, image, citation, link, underline, strike_though, bold, italic,
+        highlight, superscript, subscript, normal
+    ];
+    parsers.iter().find_map(|f| f(text, opts))
 }
 
-pub fn words(mut text: &str) -> Words {
-    if text.is_empty() { return words!(normal_word!(""))};
+/// Inline words of `text`, or `None` when some of it cannot be parsed.
+pub fn words(source: &str, opts: &ParserOptions) -> Option<Words> {
+    if source.is_empty() { return Some(Words(vec!(normal_word!("")), vec!(Span::new(0, 0)))) };
     let mut tokens: Vec<Word> = vec!();
+    let mut spans: Vec<Span> = vec!();
+    let mut text = source;
+    // Whether the last word is a bracket that opened no link, so the text
+    // after it reads on as part of the same word.
+    let mut bracket = false;
     while !text.is_empty() {
-        let result = word(&text);
-        tokens.push(result.token);
+        let mut result = word(text, opts)?;
+        if result.rest.len() >= text.len() { return None }
+        let start = source.len() - text.len();
+        let end = source.len() - result.rest.len();
+        let opens = matches!(result.token, Word::Normal(_)) && BRACKETS.iter().any(|b| text.starts_with(b));
         text = result.rest;
+        if let (Word::Normal(next), Some(Word::Normal(last)), Some(span)) = (&result.token, tokens.last_mut(), spans.last_mut()) {
+            if opens || bracket {
+                last.push_str(next);
+                *span = Span::new(span.start.offset, end);
+                bracket = opens;
+                continue
+            }
+        }
+        bracket = opens;
+        if let Some(inner) = result.token.words_mut() {
+            shift_words(inner, start);
+        }
+        tokens.push(result.token);
+        spans.push(Span::new(start, end));
     }
-    Words(tokens)
+    Some(Words(tokens, spans))
 }
 
-pub fn sentence(texts: &str) -> Option<ParsedResult<Md>> {
+pub fn sentence<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
     if texts.is_empty() { return None }
     let (text, rest) = split_first_pattern(texts, "\n");
-    let tokens = words(&text);
+    let tokens = words(text, opts)?;
     Some(ParsedResult::new(Md::Sentence(tokens), rest))
 }
 
@@ -88,12 +201,20 @@ mod tests {
     use super::*;
     use crate::{words,normal_word};
 
+    fn words(source: &str, opts: &ParserOptions) -> Option<Words> {
+        super::words(source, opts).without_spans()
+    }
+
+    fn sentence<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::sentence(texts, opts).without_spans()
+    }
+
     #[test]
     fn test_sentence() {
         let words = words!(normal_word!("Hello World!"));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello World!"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -101,7 +222,7 @@ mod tests {
         let words = words!(normal_word!("Hello World!"));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello World!"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello World!", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -110,7 +231,7 @@ mod tests {
         let words = words!(Word::Italic(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"*Hello World!*"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("*Hello World!*", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -119,7 +240,7 @@ mod tests {
         let words = words!(Word::Bold(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"**Hello World!**"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("**Hello World!**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -128,7 +249,7 @@ mod tests {
         let words = words!(Word::StrikeThough(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"~~Hello World!~~"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("~~Hello World!~~", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -137,7 +258,7 @@ mod tests {
         let words = words!(Word::Underline(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"__Hello World!__"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("__Hello World!__", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -147,14 +268,14 @@ mod tests {
         let words = words!(Word::Underline(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"__**Hello World!**__"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("__**Hello World!**__", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let words = words!(normal_word!("Hello World!"));
         let words = words!(Word::Underline(words));
         let words = words!(Word::Bold(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"**__Hello World!__**"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("**__Hello World!__**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let words = words!(normal_word!("Hello World!"));
         let words = words!(Word::Underline(words));
@@ -162,7 +283,7 @@ mod tests {
         let words = words!(Word::StrikeThough(words));
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"~~**__Hello World!__**~~"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("~~**__Hello World!__**~~", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -172,7 +293,7 @@ mod tests {
         // let words = words!(word0, word1);
         // let token = Md::Sentence(words);
         // let rest = "";
-        // assert_eq!(sentence(&"Hello **World!**"), Some(ParsedResult{token, rest}));
+        // assert_eq!(sentence("Hello **World!**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let word0 = normal_word!("Hello ");
         let word1 = normal_word!("**");
@@ -180,7 +301,7 @@ mod tests {
         let words = words!(word0, word1, word2);
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello **World!"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello **World!", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 
     #[test]
@@ -192,7 +313,7 @@ mod tests {
         let words = words!(word0, word1, word2, word3);
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello ****World!"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello ****World!", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let word0 = normal_word!("Hello ");
         let word1 = normal_word!("**");
@@ -201,7 +322,7 @@ mod tests {
         let words = words!(word0, word1, word2, word3);
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello **~~World!"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello **~~World!", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let word0 = normal_word!("~~");
         let word1 = normal_word!("World!");
@@ -210,7 +331,7 @@ mod tests {
         let words = words!(normal, bold);
         let token = Md::Sentence(words);
         let rest = "";
-        assert_eq!(sentence(&"Hello **~~World!**"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello **~~World!**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let strike = normal_word!("~~");
         let wor = normal_word!("Wor");
@@ -221,7 +342,17 @@ mod tests {
         let token = words!(hello, bold);
         let token = Md::Sentence(token);
         let rest = "";
-        assert_eq!(sentence(&"Hello **~~Wor__ld!__**"), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence("Hello **~~Wor__ld!__**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
+    }
+
+    #[test]
+    fn test_text_brackets() {
+        let token = Md::Sentence(words!(normal_word!("see [x] and ![y "), Word::Bold(words!(normal_word!("z")))));
+        let rest = "";
+        assert_eq!(sentence("see [x] and ![y **z**", &ParserOptions::default()), Some(ParsedResult{token, rest}));
+
+        let spans = super::words("a [b] c", &ParserOptions::default()).unwrap().1;
+        assert_eq!(spans, vec!(Span::new(0, 7)));
     }
 
     #[test]
@@ -231,7 +362,7 @@ mod tests {
         let token = words!(token);
         let token = Md::Sentence(token);
         let rest = " World!";
-        assert_eq!(sentence(&test_word), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence(test_word, &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let test_word = "**Hello**\n World!";
         let token = normal_word!("Hello");
@@ -239,6 +370,52 @@ mod tests {
         let token = words!(token);
         let token = Md::Sentence(token);
         let rest = " World!";
-        assert_eq!(sentence(&test_word), Some(ParsedResult{token, rest}));
+        assert_eq!(sentence(test_word, &ParserOptions::default()), Some(ParsedResult{token, rest}));
+    }
+
+    #[test]
+    fn test_highlight() {
+        let opts = ParserOptions { highlight: true, ..ParserOptions::default() };
+        let mark = Word::Highlight(words!(normal_word!("Hello")));
+        let token = words!(mark, normal_word!(" World!"));
+        assert_eq!(words("==Hello== World!", &opts), Some(token));
+
+        let token = words!(normal_word!("==Hello== World!"));
+        assert_eq!(words("==Hello== World!", &ParserOptions::default()), Some(token));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_script() {
+        let opts = ParserOptions { superscript: true, subscript: true, ..ParserOptions::default() };
+        let sup = Word::Superscript(words!(normal_word!("2")));
+        let sub = Word::Subscript(words!(normal_word!("2")));
+        let token = words!(normal_word!("x"), sup, normal_word!(" H"), sub, normal_word!("O"));
+        assert_eq!(words("x^2^ H~2~O", &opts), Some(token));
+
+        let strike = Word::StrikeThough(words!(normal_word!("gone")));
+        let sub = Word::Subscript(words!(normal_word!("i")));
+        let token = words!(strike, normal_word!(" a"), sub);
+        assert_eq!(words("~~gone~~ a~i~", &opts), Some(token));
+
+        let token = words!(normal_word!("a "), normal_word!("~"), normal_word!(" b "), normal_word!("~"), normal_word!(" c"));
+        assert_eq!(words("a ~ b ~ c", &opts), Some(token));
+
+        let token = words!(normal_word!("x^2^"));
+        assert_eq!(words("x^2^", &ParserOptions::default()), Some(token));
+    }
+    #[test]
+    fn test_code() {
+        let opts = ParserOptions::default();
+        let token = words!(normal_word!("a "), Word::Code("**b**".to_string()), normal_word!(" c"));
+        assert_eq!(words("a `**b**` c", &opts), Some(token));
+
+        let token = words!(Word::Code("a ` b".to_string()));
+        assert_eq!(words("`` a ` b ``", &opts), Some(token));
+
+        let token = words!(Word::Code("  ".to_string()));
+        assert_eq!(words("`  `", &opts), Some(token));
+
+        let token = words!(normal_word!("``"), normal_word!("a"), Word::Code("b".to_string()));
+        assert_eq!(words("``a`b`", &opts), Some(token));
+    }
+}
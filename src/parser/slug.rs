@@ -0,0 +1,139 @@
+use crate::parser::parser::*;
+use std::collections::HashMap;
+
+pub fn words_to_text(words: &Words) -> String {
+    words.0.iter().map(|word| match word {
+        Word::Normal(text) => text.clone(),
+        Word::Code(text) => text.clone(),
+        Word::Emoji { char, .. } => char.clone(),
+        Word::WikiLink { page, label } => label.clone().unwrap_or(page.clone()),
+        Word::Image { alt, .. } => alt.clone(),
+        Word::Abbreviation { abbr, .. } => abbr.clone(),
+        Word::Citation { .. } => "".to_string(),
+        word => word.words().map(words_to_text).unwrap_or_default(),
+    })
+    .collect()
+}
+
+/// Whether `c` is a combining mark, such as an accent or an Indic virama,
+/// that `is_alphanumeric` leaves out.
+fn is_mark(c: char) -> bool {
+    let code = c as u32;
+    match code {
+        0x0300..=0x036F | 0x0483..=0x0489 | 0x0591..=0x05BD | 0x05BF..=0x05C7 |
+        0x0610..=0x061A | 0x064B..=0x065F | 0x0670 | 0x06D6..=0x06ED |
+        0x0E31 | 0x0E34..=0x0E3A | 0x0E47..=0x0E4E |
+        0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF |
+        0x3099..=0x309A | 0xFE20..=0xFE2F => true,
+        // The Indic blocks share one layout, so their signs sit at the
+        // same place in each.
+        0x0900..=0x0D7F => matches!(code & 0x7F, 0x00..=0x03 | 0x3A..=0x3C | 0x3E..=0x4F | 0x51..=0x57 | 0x62..=0x63),
+        _ => false,
+    }
+}
+
+pub fn slugify(text: &str) -> String {
+    text.trim()
+        .to_lowercase()
+        .chars()
+        .filter(|c| c.is_alphanumeric() || is_mark(*c) || c == &' ' || c == &'-' || c == &'_')
+        .map(|c| if c == ' ' { '-' } else { c })
+        .collect()
+}
+
+#[derive(Debug, Default)]
+struct Slugger {
+    occurrences: HashMap<String, usize>,
+}
+
+impl Slugger {
+    fn unique(&mut self, slug: String) -> String {
+        let mut id = slug.clone();
+        while self.occurrences.contains_key(&id) {
+            let n = self.occurrences.entry(slug.clone()).or_insert(0);
+            *n += 1;
+            id = format!("{}-{}", slug, n);
+        }
+        self.occurrences.insert(id.clone(), 0);
+        id
+    }
+}
+
+pub fn heading_ids(mds: &mut [Md]) {
+    let mut slugger = Slugger::default();
+    // Explicit ids are taken first, so an earlier heading's slug can't
+    // claim one.
+    walk_mut(mds, &mut |md| {
+        if let Md::Heading(heading) = md {
+            if !heading.id.is_empty() {
+                slugger.occurrences.entry(heading.id.clone()).or_insert(0);
+            }
+        }
+    });
+    walk_mut(mds, &mut |md| {
+        if let Md::Heading(heading) = md {
+            if heading.id.is_empty() {
+                let slug = slugify(&words_to_text(&heading.words));
+                let slug = if slug.is_empty() { "section".to_string() } else { slug };
+                heading.id = slugger.unique(slug);
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    #[test]
+    fn test_slugify() {
+        assert_eq!(slugify("Hello World!"), "hello-world");
+        assert_eq!(slugify("  Foo_bar - baz? "), "foo_bar---baz");
+        assert_eq!(slugify("Ünïcödé 見出し"), "ünïcödé-見出し");
+        assert_eq!(slugify("C++ & Rust"), "c--rust");
+        assert_eq!(slugify("हिन्दी"), "हिन्दी");
+        assert_eq!(slugify("Cafe\u{301}!"), "cafe\u{301}");
+    }
+
+    #[test]
+    fn test_words_to_text() {
+        let bold = Word::Bold(words!(normal_word!("World")));
+        let words = words!(normal_word!("Hello "), bold);
+        assert_eq!(words_to_text(&words), "Hello World");
+    }
+
+    #[test]
+    fn test_heading_ids() {
+        let heading = |text: &str, id: &str| Md::Heading(Heading {
+            level: 1,
+            words: words!(normal_word!(text)),
+            id: id.to_string(),
+            attrs: Attributes::default(),
+            span: Span::default(),
+        });
+        let ids = |mds: &[Md]| -> Vec<String> {
+            mds.iter().filter_map(|md| match md {
+                Md::Heading(heading) => Some(heading.id.clone()),
+                _ => None,
+            }).collect()
+        };
+        let mut mds = vec!(
+            heading("Intro", ""),
+            heading("Intro", ""),
+            heading("Intro", ""),
+            heading("Usage", "intro-3"),
+            heading("Usage", ""),
+        );
+        heading_ids(&mut mds);
+        assert_eq!(ids(&mds), vec!("intro", "intro-1", "intro-2", "intro-3", "usage"));
+
+        let mut mds = vec!(heading("a", ""), heading("b", "a"));
+        heading_ids(&mut mds);
+        assert_eq!(ids(&mds), vec!("a-1", "a"));
+
+        let mut mds = vec!(heading("!!!", ""), heading("", ""));
+        heading_ids(&mut mds);
+        assert_eq!(ids(&mds), vec!("section", "section-1"));
+    }
+}
@@ -0,0 +1,127 @@
+use crate::parser::parser::*;
+
+const OPENERS: &str = "([{\u{2013}\u{2014}\u{201C}\u{2018}";
+const CLOSERS: &str = ".,;:!?)]}";
+const CONTRACTIONS: [&str; 7] = ["s", "t", "d", "m", "ll", "re", "ve"];
+
+fn next_char(text: &str, end: usize, after: Option<char>) -> Option<char> {
+    if end < text.len() { text[end..].chars().next() } else { after }
+}
+
+fn is_opening(prev: Option<char>, next: Option<char>) -> bool {
+    prev.is_none_or(|c| c.is_whitespace() || OPENERS.contains(c)) &&
+        next.is_some_and(|c| !c.is_whitespace() && !CLOSERS.contains(c))
+}
+
+fn is_contraction(rest: &str) -> bool {
+    let end = rest.find(|c: char| !c.is_alphabetic()).unwrap_or(rest.len());
+    CONTRACTIONS.contains(&rest[..end].to_lowercase().as_str())
+}
+
+fn single_quote(prev: Option<char>, rest: &str, next: Option<char>) -> &'static str {
+    let apostrophe = prev.is_some_and(char::is_alphanumeric) ||
+        (prev.is_none() && is_contraction(rest)) ||
+        (prev.is_none_or(char::is_whitespace) && next.is_some_and(|c| c.is_ascii_digit()));
+    if !apostrophe && is_opening(prev, next) { "\u{2018}" } else { "\u{2019}" }
+}
+
+fn double_quote(prev: Option<char>, next: Option<char>) -> &'static str {
+    if is_opening(prev, next) { "\u{201C}" } else { "\u{201D}" }
+}
+
+fn dash(prev: Option<char>, len: usize, next: Option<char>) -> Option<&'static str> {
+    let flag = prev.is_none_or(char::is_whitespace) && next.is_some_and(char::is_alphanumeric);
+    let arrow = prev.is_some_and(|c| "<!".contains(c)) || next == Some('>');
+    if flag || arrow { return None }
+    match len {
+        2 => Some("\u{2013}"),
+        3 => Some("\u{2014}"),
+        _ => None,
+    }
+}
+
+/// The length in the source of a character `smarten` may have put in place
+/// of plain punctuation.
+pub(crate) fn smartened_len(c: char) -> Option<usize> {
+    match c {
+        '\u{201C}' | '\u{201D}' | '\u{2018}' | '\u{2019}' => Some(1),
+        '\u{2013}' => Some(2),
+        '\u{2014}' | '\u{2026}' => Some(3),
+        _ => None,
+    }
+}
+
+/// Applies typographic replacements to a run of plain text. `after` is the
+/// character that follows the run in the source, if any.
+pub fn smarten(text: &str, after: Option<char>) -> String {
+    let mut out = String::new();
+    let mut prev: Option<char> = None;
+    let mut index = 0;
+    while index < text.len() {
+        let rest = &text[index..];
+        if prev.is_none_or(char::is_whitespace) {
+            let token = &rest[..rest.find(char::is_whitespace).unwrap_or(rest.len())];
+            if is_url(token) {
+                out.push_str(token);
+                index += token.len();
+                prev = token.chars().next_back();
+                continue
+            }
+        }
+        let c = rest.chars().next().unwrap_or_default();
+        let len = match c {
+            '-' | '.' => rest.chars().take_while(|x| x == &c).count(),
+            _ => c.len_utf8(),
+        };
+        let next = next_char(text, index + len, after);
+        let replacement = match c {
+            '"' => Some(double_quote(prev, next)),
+            '\'' => Some(single_quote(prev, &rest[1..], next)),
+            '-' => dash(prev, len, next),
+            '.' if len == 3 => Some("\u{2026}"),
+            _ => None,
+        };
+        out.push_str(replacement.unwrap_or(&rest[..len]));
+        prev = text[..(index + len)].chars().next_back();
+        index += len;
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quotes() {
+        assert_eq!(smarten("\"Hello,\" she said.", None), "\u{201C}Hello,\u{201D} she said.");
+        assert_eq!(smarten("'single' quotes", None), "\u{2018}single\u{2019} quotes");
+        assert_eq!(smarten("(\"nested 'inner'\")", None), "(\u{201C}nested \u{2018}inner\u{2019}\u{201D})");
+        assert_eq!(smarten("\"", Some('*')), "\u{201C}");
+        assert_eq!(smarten("\", then", None), "\u{201D}, then");
+    }
+
+    #[test]
+    fn test_apostrophes() {
+        assert_eq!(smarten("don't won't it's", None), "don\u{2019}t won\u{2019}t it\u{2019}s");
+        assert_eq!(smarten("the dogs' bowls", None), "the dogs\u{2019} bowls");
+        assert_eq!(smarten("back in '90s", None), "back in \u{2019}90s");
+        assert_eq!(smarten("'s title", None), "\u{2019}s title");
+    }
+
+    #[test]
+    fn test_dashes_and_ellipsis() {
+        assert_eq!(smarten("pages 10--20", None), "pages 10\u{2013}20");
+        assert_eq!(smarten("wait---what", None), "wait\u{2014}what");
+        assert_eq!(smarten("a -- b", None), "a \u{2013} b");
+        assert_eq!(smarten("run --verbose or ----", None), "run --verbose or ----");
+        assert_eq!(smarten("<!-- x -->", None), "<!-- x -->");
+        assert_eq!(smarten("Wait... what.... ok..", None), "Wait\u{2026} what.... ok..");
+    }
+
+    #[test]
+    fn test_urls() {
+        assert_eq!(smarten("see https://a.b/x--y...z 'ok'", None), "see https://a.b/x--y...z \u{2018}ok\u{2019}");
+        assert_eq!(smarten("www.a.b/it's", None), "www.a.b/it's");
+    }
+}
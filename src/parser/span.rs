@@ -0,0 +1,233 @@
+use std::ops::Range;
+use crate::parser::parser::*;
+use super::smart::smartened_len;
+
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Point {
+    /// Byte offset into the parsed text.
+    pub offset: usize,
+    /// 1-based line, or 0 until `locate` has run.
+    pub line: usize,
+    /// 1-based column, counted in characters.
+    pub column: usize,
+}
+
+/// Where a node came from. Parsers record spans relative to the text they
+/// were given, and callers shift them as results nest, so the spans of a
+/// parsed document are offsets into the whole document.
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct Span {
+    pub start: Point,
+    pub end: Point,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Span {
+        let point = |offset| Point { offset, ..Point::default() };
+        Span { start: point(start), end: point(end) }
+    }
+
+    pub fn range(&self) -> Range<usize> {
+        self.start.offset..self.end.offset
+    }
+
+    /// The spans from the start of the first to the end of the last.
+    pub fn cover(spans: &[Span]) -> Span {
+        match (spans.first(), spans.last()) {
+            (Some(first), Some(last)) => Span { start: first.start, end: last.end },
+            _ => Span::default(),
+        }
+    }
+
+    pub(crate) fn map(&mut self, f: &dyn Fn(usize) -> usize) {
+        *self = Span::new(f(self.start.offset), f(self.end.offset));
+    }
+
+    /// The part covering `range` of `text`, where this span covers exactly
+    /// `text`, or the source `text` was smartened from. Text that was
+    /// otherwise rewritten or spans lines keeps the whole span.
+    pub(crate) fn slice(&self, text: &str, range: Range<usize>) -> Span {
+        if text.contains('\n') { return *self }
+        let Some(source) = source_offsets(text, self.range().len()) else { return *self };
+        let point = |i: usize| {
+            let (offset, chars) = source[text[..i].chars().count()];
+            Point {
+                offset: self.start.offset + offset,
+                line: self.start.line,
+                column: if self.start.line == 0 { 0 } else { self.start.column + chars },
+            }
+        };
+        Span { start: point(range.start), end: point(range.end) }
+    }
+}
+
+/// The source byte offset and character count before each character of
+/// `text`, and after the last, when `text` is `len` bytes of source as is or
+/// once smart punctuation is undone.
+fn source_offsets(text: &str, len: usize) -> Option<Vec<(usize, usize)>> {
+    let smartened = text.len() != len;
+    let mut offsets = vec!((0, 0));
+    let (mut offset, mut chars) = (0, 0);
+    for c in text.chars() {
+        let size = if smartened { smartened_len(c) } else { None };
+        offset += size.unwrap_or(c.len_utf8());
+        chars += size.unwrap_or(1);
+        offsets.push((offset, chars));
+    }
+    (offset == len).then_some(offsets)
+}
+
+/// Clears the spans of a tree, so it compares equal to one built by hand.
+pub trait WithoutSpans {
+    fn without_spans(self) -> Self;
+}
+
+fn clear(span: &mut Span) {
+    *span = Span::default();
+}
+
+impl WithoutSpans for Md {
+    fn without_spans(self) -> Md {
+        let mut mds = [self];
+        walk_spans_mut(&mut mds, &mut clear);
+        let [md] = mds;
+        md
+    }
+}
+
+impl WithoutSpans for Words {
+    fn without_spans(mut self) -> Words {
+        words_spans_mut(&mut self, &mut clear);
+        self
+    }
+}
+
+impl WithoutSpans for Word {
+    fn without_spans(mut self) -> Word {
+        if let Some(words) = self.words_mut() {
+            words_spans_mut(words, &mut clear);
+        }
+        self
+    }
+}
+
+impl WithoutSpans for Item {
+    fn without_spans(self) -> Item {
+        Item(self.0.without_spans(), self.1, Span::default())
+    }
+}
+
+impl WithoutSpans for Items {
+    fn without_spans(self) -> Items {
+        Items(self.0.without_spans())
+    }
+}
+
+impl WithoutSpans for Record {
+    fn without_spans(self) -> Record {
+        Record(self.0.without_spans(), Span::default())
+    }
+}
+
+impl WithoutSpans for List {
+    fn without_spans(self) -> List {
+        List { items: self.items.without_spans(), loose: self.loose, span: Span::default() }
+    }
+}
+
+impl<T: WithoutSpans> WithoutSpans for (T, bool) {
+    fn without_spans(self) -> (T, bool) {
+        (self.0.without_spans(), self.1)
+    }
+}
+
+impl<T: WithoutSpans> WithoutSpans for (usize, T) {
+    fn without_spans(self) -> (usize, T) {
+        (self.0, self.1.without_spans())
+    }
+}
+
+impl<T: WithoutSpans> WithoutSpans for Vec<T> {
+    fn without_spans(self) -> Vec<T> {
+        self.into_iter().map(T::without_spans).collect()
+    }
+}
+
+impl<T: WithoutSpans> WithoutSpans for Option<T> {
+    fn without_spans(self) -> Option<T> {
+        self.map(T::without_spans)
+    }
+}
+
+impl<T: WithoutSpans, E> WithoutSpans for Result<T, E> {
+    fn without_spans(self) -> Result<T, E> {
+        self.map(T::without_spans)
+    }
+}
+
+impl<T: WithoutSpans> WithoutSpans for ParsedResult<'_, T> {
+    fn without_spans(self) -> Self {
+        ParsedResult::new(self.token.without_spans(), self.rest)
+    }
+}
+
+/// Byte offset of `slice` within `text`, which it must be borrowed from.
+pub fn offset_in(text: &str, slice: &str) -> usize {
+    (slice.as_ptr() as usize).saturating_sub(text.as_ptr() as usize).min(text.len())
+}
+
+pub(crate) fn shift(mds: &mut [Md], by: usize) {
+    walk_spans_mut(mds, &mut |span| span.map(&|offset| offset + by));
+}
+
+pub(crate) fn shift_words(words: &mut Words, by: usize) {
+    words_spans_mut(words, &mut |span| span.map(&|offset| offset + by));
+}
+
+/// Fills in the lines and columns of every span in `mds` from its offsets
+/// into `source`.
+pub fn locate(mds: &mut [Md], source: &str) {
+    let starts: Vec<usize> = std::iter::once(0)
+        .chain(source.match_indices('\n').map(|(i, _)| i + 1))
+        .collect();
+    let point = |offset: usize| {
+        let mut offset = offset.min(source.len());
+        while !source.is_char_boundary(offset) { offset -= 1 }
+        let line = starts.partition_point(|start| *start <= offset);
+        let column = source[starts[line-1]..offset].chars().count() + 1;
+        Point { offset, line, column }
+    };
+    walk_spans_mut(mds, &mut |span| {
+        span.start = point(span.start.offset);
+        span.end = point(span.end.offset);
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_slice() {
+        let span = Span::new(10, 18);
+        assert_eq!(span.slice("héllo w", 3..7).range(), 13..17);
+        assert_eq!(span.slice("rewritten", 3..7).range(), 10..18);
+        assert_eq!(Span::new(10, 17).slice("\u{201C}a\u{201D} b\u{2013}", 3..9).range(), 11..15);
+
+        let mut located = span;
+        located.start = Point { offset: 10, line: 2, column: 5 };
+        assert_eq!(located.slice("héllo w", 3..7).start, Point { offset: 13, line: 2, column: 7 });
+    }
+
+    #[test]
+    fn test_locate() {
+        let source = "# Tïtle\n\n- one\n- two";
+        let mds = parse(source).unwrap();
+        let span = mds[2].span();
+        assert_eq!((span.start, span.end), (
+            Point { offset: 10, line: 3, column: 1 },
+            Point { offset: 21, line: 4, column: 6 },
+        ));
+        assert_eq!(offset_in(source, &source[3..]), 3);
+    }
+}
@@ -8,7 +8,7 @@ fn record<'a, T>(
 ) -> Option<ParsedResult<'a, Vec<T>>> {
     let (text, rest) = split_first_pattern(texts, "\n");
     let text = text.trim_end();
-    if !text.starts_with("|") || !text.ends_with("|") { return None }
+    if text.len() < 2 || !text.starts_with("|") || !text.ends_with("|") { return None }
 
     let end = text.len()-1;
     let token: Vec<T> = text[1..end].split("|")
@@ -17,20 +17,32 @@ fn record<'a, T>(
     Some(ParsedResult::new(token, rest))
 }
 
-fn header(texts: &str) -> Option<ParsedResult<Record>> {
-    let cells = record(
-        texts, &|txt| words(txt)
-    )?;
-    let record = Record(cells.token);
+/// The span of the row that starts `texts`, relative to `input`.
+fn row_span(input: &str, texts: &str) -> Span {
+    let line = split_first_pattern(texts, "\n").0.trim_end();
+    let start = offset_in(input, line);
+    Span::new(start, start + line.len())
+}
+
+/// The words of a cell, which is a slice of `texts`.
+fn cell(texts: &str, text: &str, opts: &ParserOptions) -> Option<Words> {
+    let mut words = words(text, opts)?;
+    shift_words(&mut words, offset_in(texts, text));
+    Some(words)
+}
+
+fn header<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Record>> {
+    let cells = record(texts, &|text| cell(texts, text, opts))?;
+    let record = Record(cells.token.into_iter().collect::<Option<_>>()?, row_span(texts, texts));
     Some(ParsedResult::new(record, cells.rest))
 }
 
-fn align(texts: &str, num: usize) -> Option<ParsedResult<Vec<Align>>> {
+fn align(texts: &str, num: usize) -> Option<ParsedResult<'_, Vec<Align>>> {
     let result = record(
         texts, &|text| align_parse(text.trim())
     )?;
     let aligns: Vec<Align> = result.token.into_iter()
-        .filter_map(|opt| opt)
+        .flatten()
         .collect();
     if aligns.len() != num { return None }
     Some(ParsedResult::new(aligns, result.rest))
@@ -44,7 +56,7 @@ fn align_parse(text: &str) -> Option<Align> {
         chars.len() == 1 && chars.contains(&'-')
     };
     match (l, r) {
-        (false, false) if is_only_hyphen(&text)                        => Some(Align::Left),
+        (false, false) if is_only_hyphen(text)                         => Some(Align::Left),
         (false, true)  if is_only_hyphen(&text[..text.len()-1])  => Some(Align::Right),
         (true, false)  if is_only_hyphen(&text[1..])             => Some(Align::Left),
         (true, true)   if is_only_hyphen(&text[1..text.len()-1]) => Some(Align::Center),
@@ -52,14 +64,15 @@ fn align_parse(text: &str) -> Option<Align> {
     }
 }
 
-fn records(mut texts: &str, n: usize) -> Option<ParsedResult<Vec<Record>>> {
+fn records<'a>(input: &'a str, n: usize, opts: &ParserOptions) -> Option<ParsedResult<'a, Vec<Record>>> {
     let mut records:Vec<Record> = vec!();
-    while let Some(result) = record(texts, &|text| words(text)) 
-    {
-        texts = result.rest;
-        let cells = result.token;
+    let mut texts = input;
+    while let Some(result) = record(texts, &|text| cell(input, text, opts)) {
+        let Some(cells) = result.token.into_iter().collect::<Option<Vec<_>>>() else { break };
         if cells.len()!=n { break; }
-        let record = Record(cells);
+        let span = row_span(input, texts);
+        texts = result.rest;
+        let record = Record(cells, span);
         records.push(record);
     }
     if records.is_empty() { return None }
@@ -67,22 +80,28 @@ fn records(mut texts: &str, n: usize) -> Option<ParsedResult<Vec<Record>>> {
 }
 fn record_len(record: &Record) -> usize {
     match record {
-        Record(r) => r.len()
+        Record(r, _) => r.len()
     }
 }
 
-pub fn table(texts: &str) -> Option<ParsedResult<Md>> {
-    let header_result = header(texts)?;
+pub fn table<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.tables { return None }
+    let header_result = header(texts, opts)?;
     let header = header_result.token;
     let column_num = record_len(&header);
 
     let align_result = align(header_result.rest, column_num)?;
     let align = align_result.token;
 
-    let records_result = records(align_result.rest, column_num)?;
+    let mut records_result = records(align_result.rest, column_num, opts)?;
+    let offset = texts.len() - align_result.rest.len();
+    for record in records_result.token.iter_mut() {
+        record.1.map(&|start| start + offset);
+        record.0.iter_mut().for_each(|cell| shift_words(cell, offset));
+    }
     let records = records_result.token;
 
-    let token = Md::Table(Box::new(Table{header, align, records}));
+    let token = Md::Table(Box::new(Table{header, align, records, span: Span::default()}));
     Some(ParsedResult::new(token, records_result.rest))
 }
 
@@ -91,6 +110,18 @@ mod tests {
     use super::*;
     use crate::{words,record,normal_word};
 
+    fn header<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Record>> {
+        super::header(texts, opts).without_spans()
+    }
+
+    fn records<'a>(input: &'a str, n: usize, opts: &ParserOptions) -> Option<ParsedResult<'a, Vec<Record>>> {
+        super::records(input, n, opts).without_spans()
+    }
+
+    fn table<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        super::table(texts, opts).without_spans()
+    }
+
     #[test]
     fn test_header() {
         let a = words!(normal_word!("A"));
@@ -98,24 +129,24 @@ mod tests {
         let c = words!(normal_word!("C"));
         let token = record!(a, b, c);
         let rest = "";
-        assert_eq!(header(&"| A | B | C | \n"), Some(ParsedResult{token, rest}));
+        assert_eq!(header("| A | B | C | \n", &ParserOptions::default()), Some(ParsedResult{token, rest}));
 
         let nul = words!(normal_word!(""));
         let b = words!(normal_word!("B"));
         let c = words!(normal_word!("C"));
         let token = record!(nul, b, c);
         let rest = "";
-        assert_eq!(header(&"|  | B | C |\n"), Some(ParsedResult{token, rest}));
-        assert_eq!(header(&"| A | B | C \n"), None);
+        assert_eq!(header("|  | B | C |\n", &ParserOptions::default()), Some(ParsedResult{token, rest}));
+        assert_eq!(header("| A | B | C \n", &ParserOptions::default()), None);
     }
 
     #[test]
     fn test_align() {
         let token = vec!(Align::Right, Align::Center, Align::Left, Align::Left);
         let rest = "";
-        assert_eq!(align(&"| -: | :-: | :- | --- |\n", 4), Some(ParsedResult{token, rest}));
-        assert_eq!(align(&"| -: | :-b: | :- | - |\n", 4), None);
-        assert_eq!(align(&"|  | :-: | :- | - |\n", 4), None);
+        assert_eq!(align("| -: | :-: | :- | --- |\n", 4), Some(ParsedResult{token, rest}));
+        assert_eq!(align("| -: | :-b: | :- | - |\n", 4), None);
+        assert_eq!(align("|  | :-: | :- | - |\n", 4), None);
     }
 
     #[test]
@@ -134,7 +165,11 @@ mod tests {
         let record2 = record!(j, k, l);
         let token = vec!(record0, record1, record2);
         let rest = "";
-        assert_eq!(records(&"| A | B | C |\n| a | b | c |\n| j | k | l |\n", 3), Some(ParsedResult{token, rest}));
+        assert_eq!(records("| A | B | C |\n| a | b | c |\n| j | k | l |\n", 3, &ParserOptions::default()), Some(ParsedResult{token, rest}));
+
+        let token = vec!(record!(words!(normal_word!("c")), words!(normal_word!("d"))));
+        let rest = "| x |\nafter";
+        assert_eq!(records("| c | d |\n| x |\nafter", 2, &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
     #[test]
     fn test_table() {
@@ -154,8 +189,8 @@ mod tests {
         let record1 = record!(j, k, l);
         let records = vec!(record0, record1);
 
-        let token = Md::Table(Box::new(Table{header, align, records}));
+        let token = Md::Table(Box::new(Table{header, align, records, span: Span::default()}));
         let rest = "";
-        assert_eq!(table(&"| A | B | C | \n|-:|--|:-:|\n| a | b | c |\n| j | k | l |\n"), Some(ParsedResult{token, rest}));
+        assert_eq!(table("| A | B | C | \n|-:|--|:-:|\n| a | b | c |\n| j | k | l |\n", &ParserOptions::default()), Some(ParsedResult{token, rest}));
     }
 }
\ No newline at end of file
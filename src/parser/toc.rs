@@ -0,0 +1,105 @@
+use crate::parser::parser::*;
+use super::slug::words_to_text;
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TocOptions {
+    pub min_level: usize,
+    pub max_level: usize,
+}
+
+impl Default for TocOptions {
+    fn default() -> Self {
+        TocOptions { min_level: 1, max_level: 6 }
+    }
+}
+
+#[derive(Debug, PartialEq, Clone)]
+pub struct TocEntry {
+    pub level: usize,
+    pub id: String,
+    pub text: String,
+    pub children: Vec<TocEntry>,
+}
+
+fn insert(entries: &mut Vec<TocEntry>, entry: TocEntry) {
+    match entries.last_mut() {
+        Some(last) if last.level < entry.level => insert(&mut last.children, entry),
+        _ => entries.push(entry),
+    }
+}
+
+pub fn toc(mds: &[Md], opts: &TocOptions) -> Vec<TocEntry> {
+    let mut entries: Vec<TocEntry> = vec!();
+    walk(mds, &mut |md| {
+        let Md::Heading(heading) = md else { return };
+        if heading.level < opts.min_level || heading.level > opts.max_level { return }
+        let entry = TocEntry {
+            level: heading.level,
+            id: heading.id.clone(),
+            text: words_to_text(&heading.words),
+            children: vec!(),
+        };
+        insert(&mut entries, entry);
+    });
+    entries
+}
+
+pub fn toc_marker<'a>(texts: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+    if !opts.toc { return None }
+    let (text, rest) = split_first_pattern(texts, "\n");
+    if text.trim() != "[TOC]" { return None }
+    Some(ParsedResult::new(Md::Toc(Span::default()), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn heading(level: usize, text: &str) -> Md {
+        let words = words!(normal_word!(text));
+        Md::Heading(Heading { level, words, id: text.to_lowercase(), attrs: Attributes::default(), span: Span::default() })
+    }
+
+    fn entry(level: usize, text: &str, children: Vec<TocEntry>) -> TocEntry {
+        TocEntry { level, id: text.to_lowercase(), text: text.to_string(), children }
+    }
+
+    #[test]
+    fn test_toc() {
+        let mds = vec!(
+            heading(1, "A"),
+            heading(2, "B"),
+            heading(3, "C"),
+            heading(2, "D"),
+            heading(1, "E"),
+        );
+        let c = entry(3, "C", vec!());
+        let b = entry(2, "B", vec!(c));
+        let d = entry(2, "D", vec!());
+        let a = entry(1, "A", vec!(b, d));
+        let e = entry(1, "E", vec!());
+        assert_eq!(toc(&mds, &TocOptions::default()), vec!(a, e));
+
+        let opts = TocOptions { min_level: 2, max_level: 2 };
+        let b = entry(2, "B", vec!());
+        let d = entry(2, "D", vec!());
+        assert_eq!(toc(&mds, &opts), vec!(b, d));
+    }
+
+    #[test]
+    fn test_toc_skipped_level() {
+        let mds = vec!(heading(1, "A"), heading(3, "B"), heading(2, "C"));
+        let b = entry(3, "B", vec!());
+        let c = entry(2, "C", vec!());
+        let a = entry(1, "A", vec!(b, c));
+        assert_eq!(toc(&mds, &TocOptions::default()), vec!(a));
+    }
+
+    #[test]
+    fn test_toc_marker() {
+        let opts = ParserOptions::default();
+        assert_eq!(toc_marker("[TOC]\nHello", &opts), Some(ParsedResult{token: Md::Toc(Span::default()), rest: "Hello"}));
+        assert_eq!(toc_marker("[TOC] here", &opts), None);
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+use crate::parser::parser::*;
+use super::span::shift;
+
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct VariableOptions {
+    /// Values from a project config, overridden by front matter.
+    pub config: Vec<(String, String)>,
+    /// `--define key=value` values, which override everything else.
+    pub defines: Vec<(String, String)>,
+    /// Report an undefined `{{ name }}` as an error instead of leaving it as written.
+    pub strict: bool,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum VariableError {
+    Undefined(String),
+    Parse(ParseError),
+}
+
+impl fmt::Display for VariableError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            VariableError::Undefined(name) => write!(f, "undefined variable: {}", name),
+            VariableError::Parse(error) => error.fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for VariableError {}
+
+fn unquote(value: &str) -> &str {
+    let value = value.trim();
+    ['"', '\''].iter()
+        .find_map(|q| value.strip_prefix(*q).and_then(|v| v.strip_suffix(*q)))
+        .unwrap_or(value)
+}
+
+fn pair(line: &str, separator: char) -> Option<(String, String)> {
+    let (key, value) = line.split_once(separator)?;
+    let key = key.trim();
+    if key.is_empty() || key.contains(char::is_whitespace) { return None }
+    Some((key.to_string(), unquote(value).to_string()))
+}
+
+/// Splits `key: value` pairs out of a leading `---` block, returning the
+/// rest of the document. Lines that are not flat pairs are ignored.
+pub fn front_matter(text: &str) -> (Vec<(String, String)>, &str) {
+    let Some(body) = consume(text, "---\n") else { return (vec!(), text) };
+    let (matter, rest) = match body.find("\n---") {
+        Some(n) => (&body[..n], split_first_pattern(&body[(n+4)..], "\n").1),
+        None if body.starts_with("---") => ("", split_first_pattern(body, "\n").1),
+        None => return (vec!(), text),
+    };
+    let pairs = matter.lines()
+        .filter(|line| !line.starts_with([' ', '\t', '#', '-']))
+        .filter_map(|line| pair(line, ':'))
+        .collect();
+    (pairs, rest)
+}
+
+/// `key = value` lines of a project config; `#` starts a comment line.
+pub fn config(text: &str) -> Vec<(String, String)> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| pair(line, '='))
+        .collect()
+}
+
+/// A `key=value` argument to `--define`.
+pub fn define(text: &str) -> Option<(String, String)> {
+    pair(text, '=')
+}
+
+fn lookup<'a>(name: &str, sources: &[&'a [(String, String)]]) -> Option<&'a str> {
+    sources.iter().find_map(|pairs| {
+        pairs.iter().rev().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    })
+}
+
+fn placeholder(text: &str) -> Option<(&str, usize)> {
+    let inner = consume(text, "{{")?;
+    let end = inner.find("}}")?;
+    let name = inner[..end].trim();
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_alphanumeric() || "_-.".contains(c));
+    valid.then_some((name, 2 + end + 2))
+}
+
+fn replace(text: &str, sources: &[&[(String, String)]], strict: bool) -> Result<String, VariableError> {
+    let mut out = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        out.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let Some((name, len)) = placeholder(rest) else {
+            out.push_str("{{");
+            rest = &rest[2..];
+            continue
+        };
+        match lookup(name, sources) {
+            Some(value) => out.push_str(value),
+            None if strict => return Err(VariableError::Undefined(name.to_string())),
+            None => out.push_str(&rest[..len]),
+        }
+        rest = &rest[len..];
+    }
+    out.push_str(rest);
+    Ok(out)
+}
+
+/// Fills `{{ name }}` in plain text, earlier `sources` taking precedence.
+/// Code spans and code blocks are left alone because they never hold plain text.
+pub fn substitute(mds: &mut [Md], sources: &[&[(String, String)]], strict: bool) -> Result<(), VariableError> {
+    let mut error: Option<VariableError> = None;
+    walk_words_mut(mds, &mut |words| {
+        for word in words.0.iter_mut() {
+            let Word::Normal(text) = word else { continue };
+            if error.is_some() || !text.contains("{{") { continue }
+            match replace(text, sources, strict) {
+                Ok(replaced) => *text = replaced,
+                Err(e) => error = Some(e),
+            }
+        }
+    });
+    error.map_or(Ok(()), Err)
+}
+
+/// Parses `text`, taking variables from its front matter as well as `vars`.
+pub fn parse_with_variables(text: &str, opts: &ParserOptions, vars: &VariableOptions) -> Result<Vec<Md>, VariableError> {
+    let (matter, body) = front_matter(text);
+    let mut mds = parse_blocks(body, opts).map_err(|mut error| {
+        error.line += text[..(text.len() - body.len())].matches('\n').count();
+        VariableError::Parse(error)
+    })?;
+    substitute(&mut mds, &[&vars.defines, &matter, &vars.config], vars.strict)?;
+    document_passes(&mut mds, opts);
+    shift(&mut mds, text.len() - body.len());
+    locate(&mut mds, text);
+    Ok(mds)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    fn pairs(pairs: &[(&str, &str)]) -> Vec<(String, String)> {
+        pairs.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn test_front_matter() {
+        let text = "---\ntitle: \"My Doc\"\nversion: 1.2\ntags:\n  - a\n---\n# Body";
+        assert_eq!(front_matter(text), (pairs(&[("title", "My Doc"), ("version", "1.2"), ("tags", "")]), "# Body"));
+        assert_eq!(front_matter("---\n---\nBody"), (vec!(), "Body"));
+        assert_eq!(front_matter("---\nno end"), (vec!(), "---\nno end"));
+        assert_eq!(front_matter("Body"), (vec!(), "Body"));
+    }
+
+    #[test]
+    fn test_config() {
+        assert_eq!(config("# project\nname = app\n\nurl = 'https://x.y'\nbad line"), pairs(&[("name", "app"), ("url", "https://x.y")]));
+        assert_eq!(define("version=2"), Some(("version".to_string(), "2".to_string())));
+        assert_eq!(define("version"), None);
+    }
+
+    #[test]
+    fn test_replace() {
+        let high = pairs(&[("name", "cli")]);
+        let low = pairs(&[("name", "config"), ("version", "1.0")]);
+        let sources: [&[(String, String)]; 2] = [&high, &low];
+        assert_eq!(replace("{{ name }} {{version}} {{ missing }} {{ not valid }} {{", &sources, false),
+            Ok("cli 1.0 {{ missing }} {{ not valid }} {{".to_string()));
+        assert_eq!(replace("{{ missing }}", &sources, true), Err(VariableError::Undefined("missing".to_string())));
+    }
+
+    #[test]
+    fn test_substitute() {
+        let vars = pairs(&[("v", "2")]);
+        let code = Word::Code("{{ v }}".to_string());
+        let block = Md::Code(CodeBlock { lang: "".to_string(), code: "{{ v }}\n".to_string(), attrs: Attributes::default(), span: Span::default() });
+        let mut mds = vec!(Md::Sentence(words!(normal_word!("v{{ v }} "), code)), block);
+        substitute(&mut mds, &[&vars], true).unwrap();
+
+        let code = Word::Code("{{ v }}".to_string());
+        let block = Md::Code(CodeBlock { lang: "".to_string(), code: "{{ v }}\n".to_string(), attrs: Attributes::default(), span: Span::default() });
+        assert_eq!(mds, vec!(Md::Sentence(words!(normal_word!("v2 "), code)), block));
+    }
+}
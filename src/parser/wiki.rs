@@ -0,0 +1,59 @@
+use crate::parser::parser::*;
+
+pub(crate) fn wiki_link<'a>(text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+    if !opts.wiki_links { return None }
+    let text = consume(text, "[[")?;
+    let index = text.find("]]")?;
+    let (inner, rest) = (&text[..index], &text[(index+2)..]);
+    let (page, label) = match inner.split_once('|') {
+        Some((page, label)) => (page.trim(), Some(label.trim().to_string())),
+        None => (inner.trim(), None),
+    };
+    if page.is_empty() || page.contains('[') { return None }
+    let token = Word::WikiLink { page: page.to_string(), label };
+    Some(ParsedResult::new(token, rest))
+}
+
+/// Pages linked from `mds`, in order of appearance, for building backlinks.
+pub fn pages(mds: &[Md]) -> Vec<String> {
+    let mut pages: Vec<String> = vec!();
+    walk_words(mds, &mut |words| {
+        for word in words.0.iter() {
+            if let Word::WikiLink { page, .. } = word {
+                pages.push(page.clone());
+            }
+        }
+    });
+    pages
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{words,normal_word};
+
+    #[test]
+    fn test_wiki_link() {
+        let opts = ParserOptions { wiki_links: true, ..ParserOptions::default() };
+        let token = Word::WikiLink { page: "Page Name".to_string(), label: None };
+        let rest = " after";
+        assert_eq!(wiki_link("[[Page Name]] after", &opts), Some(ParsedResult{token, rest}));
+
+        let token = Word::WikiLink { page: "Page Name".to_string(), label: Some("label".to_string()) };
+        let rest = "";
+        assert_eq!(wiki_link("[[ Page Name | label ]]", &opts), Some(ParsedResult{token, rest}));
+
+        assert_eq!(wiki_link("[[]]", &opts), None);
+        assert_eq!(wiki_link("[[Page", &opts), None);
+        assert_eq!(wiki_link("[[Page]]", &ParserOptions::default()), None);
+    }
+
+    #[test]
+    fn test_pages() {
+        let home = Word::WikiLink { page: "Home".to_string(), label: None };
+        let faq = Word::WikiLink { page: "FAQ".to_string(), label: Some("questions".to_string()) };
+        let bold = Word::Bold(words!(faq));
+        let mds = vec!(Md::Sentence(words!(normal_word!("See "), home, bold)));
+        assert_eq!(pages(&mds), vec!("Home".to_string(), "FAQ".to_string()));
+    }
+}
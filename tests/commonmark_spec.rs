@@ -0,0 +1,38 @@
+//! Runs the examples of the CommonMark spec, vendored from
+//! <https://github.com/commonmark/commonmark-spec> at version 0.28, through
+//! the CommonMark profile and reports the pass rate of each section. Run
+//! with `--nocapture` to see the report.
+
+mod spec;
+
+use app::parser::parser::*;
+use app::convert::convert::mds_to_html;
+
+/// Examples known to pass. Raise it as the parser improves; the suite fails
+/// when fewer pass.
+const PASSING: usize = 48;
+
+#[test]
+fn test_commonmark_spec() {
+    let examples = spec::examples(include_str!("spec/commonmark.txt"));
+    assert_eq!(examples.len(), 633);
+    let render = |markdown: &str| {
+        let mds = parse_with(markdown, &ParserOptions::commonmark())?;
+        Ok(mds_to_html(&mds))
+    };
+    let report = spec::run("CommonMark 0.28", &examples, &render);
+    assert!(report.panics.is_empty(), "examples {:?} panicked", report.panics);
+    assert!(report.errors.is_empty(), "examples {:?} failed to parse", report.errors);
+    assert!(report.passed >= PASSING, "{} examples pass, down from {}", report.passed, PASSING);
+}
+
+#[test]
+fn test_normalize() {
+    assert_eq!(spec::normalize("<p>a  \t\nb</p>"), "<p>a b</p>");
+    assert_eq!(spec::normalize("\n\t<p>\n\t\ta  b\t\t</p>\n\t"), "<p>a b</p>");
+    assert_eq!(spec::normalize("<i>a  b</i> "), "<i>a b</i> ");
+    assert_eq!(spec::normalize("a<br />\nb"), "a<br>b");
+    assert_eq!(spec::normalize("<a title=\"bar\" HREF=\"foo\">x</a>"), "<a href=\"foo\" title=\"bar\">x</a>");
+    assert_eq!(spec::normalize("&#8704;&amp;&gt;&lt;&quot;&#x41;"), "\u{2200}&amp;&gt;&lt;&quot;A");
+    assert_eq!(spec::normalize("<pre><code>a  b\n</code></pre>\n"), "<pre><code>a  b\n</code></pre>");
+}
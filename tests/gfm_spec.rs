@@ -0,0 +1,52 @@
+//! Runs the extension examples of the GitHub Flavored Markdown spec,
+//! vendored from <https://github.com/github/cmark-gfm> at version 0.28-gfm,
+//! through the GFM profile and reports the pass rate of each extension. Run
+//! with `--nocapture` to see the report. Upstream marks the task list
+//! examples `disabled`; they run here with the others.
+
+mod spec;
+
+use app::parser::parser::*;
+use app::convert::convert::mds_to_html;
+
+fn render(markdown: &str) -> Result<String, ParseError> {
+    let mds = parse_with(markdown, &ParserOptions::gfm())?;
+    Ok(mds_to_html(&mds))
+}
+
+/// Examples known to pass. Raise it as the parser improves; the suite fails
+/// when fewer pass.
+const PASSING: usize = 2;
+
+#[test]
+fn test_gfm_spec() {
+    let examples: Vec<spec::Example> = spec::examples(include_str!("spec/gfm.txt")).into_iter()
+        .filter(|example| example.extension.is_some())
+        .collect();
+    let report = spec::run("GFM 0.28 extensions", &examples, &render);
+    assert!(report.panics.is_empty(), "examples {:?} panicked", report.panics);
+    assert!(report.errors.is_empty(), "examples {:?} failed to parse", report.errors);
+    assert!(report.passed >= PASSING, "{} examples pass, down from {}", report.passed, PASSING);
+}
+
+/// Table examples where `table.rs` finds other rows or cells than the spec.
+/// Each one is a known difference in behavior, not in rendering.
+const TABLE_DIFFERENCES: [(usize, &str); 5] = [
+    (194, "rows need leading and trailing pipes"),
+    (195, "escaped pipes still split cells"),
+    (197, "the table ends at the first line that is not a row, rather than at a blank line"),
+    (199, "a row with another cell count ends the table and is parsed as the next block, rather than being padded or cut"),
+    (200, "a table needs at least one body row"),
+];
+
+#[test]
+fn test_gfm_tables() {
+    let examples = spec::examples(include_str!("spec/gfm.txt"));
+    let tables = examples.iter().filter(|example| example.extension.as_deref() == Some("table"));
+    let differing: Vec<usize> = tables
+        .filter(|example| spec::table_rows(&render(&example.markdown).unwrap_or_default()) != spec::table_rows(&example.html))
+        .map(|example| example.number)
+        .collect();
+    let known: Vec<usize> = TABLE_DIFFERENCES.iter().map(|(number, _)| *number).collect();
+    assert_eq!(differing, known);
+}
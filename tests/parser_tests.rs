@@ -1,6 +1,14 @@
 use app::parser::parser::*;
 use app::{items,words,normal_word};
 
+fn parse(text: &str) -> Result<Vec<Md>, ParseError> {
+    app::parser::parser::parse(text).without_spans()
+}
+
+fn parse_with(text: &str, opts: &ParserOptions) -> Result<Vec<Md>, ParseError> {
+    app::parser::parser::parse_with(text, opts).without_spans()
+}
+
 #[test]
 fn test_parser() {
     let normal = words!(normal_word!("Hello World!"));
@@ -8,37 +16,37 @@ fn test_parser() {
     let bold_line = Word::Underline(bold);
     let words = words!(bold_line);
     let md = Md::Sentence(words);
-    assert_eq!(parse(&"__**Hello World!**__"), vec!(md));
+    assert_eq!(parse("__**Hello World!**__"), Ok(vec!(md)));
 
     let normal = words!(normal_word!("Hello World!"));
     let line = words!(Word::Underline(normal));
     let line_bold = Word::Bold(line);
     let words = words!(line_bold);
     let md = Md::Sentence(words);
-    assert_eq!(parse(&"**__Hello World!__**"), vec!(md));
+    assert_eq!(parse("**__Hello World!__**"), Ok(vec!(md)));
 
     let normal = words!(normal_word!("Hello World!"));
     let line_normal = words!(Word::Underline(normal));
     let bold_line_normal = words!(Word::Bold(line_normal));
     let strike_bold_line_normal = words!(Word::StrikeThough(bold_line_normal));
     let md = Md::Sentence(strike_bold_line_normal);
-    assert_eq!(parse(&"~~**__Hello World!__**~~"), vec!(md));
+    assert_eq!(parse("~~**__Hello World!__**~~"), Ok(vec!(md)));
 
     let hello = normal_word!("Hello ");
     let world = Word::Bold(words!(normal_word!("World!")));
     let word = words!(hello, world);
     let md = Md::Sentence(word);
-    assert_eq!(parse(&"Hello **World!**"), vec!(md));
+    assert_eq!(parse("Hello **World!**"), Ok(vec!(md)));
 
     let normal = words!(normal_word!("Hello World!"));
-    let md = Md::Heading(1, normal);
-    assert_eq!(parse(&"# Hello World!"), vec!(md));
+    let md = Md::Heading(Heading { level: 1, words: normal, id: "hello-world".to_string(), attrs: Attributes::default(), span: Span::default() });
+    assert_eq!(parse("# Hello World!"), Ok(vec!(md)));
 }
 
 #[test]
 fn test_parsing_multiline() {
     let hello_world = words!(normal_word!("Hello World!"));
-    let head: Md = Md::Heading(1, hello_world);
+    let head: Md = Md::Heading(Heading { level: 1, words: hello_world, id: "hello-world".to_string(), attrs: Attributes::default(), span: Span::default() });
 
     let words = words!(normal_word!("rust parser"));
     let sentence = Md::Sentence(words);
@@ -47,36 +55,450 @@ fn test_parsing_multiline() {
     let bold_sentence = Md::Sentence(bold);
 
     let mds = vec!(head, sentence, bold_sentence);
-    assert_eq!(parse(&"# Hello World!\nrust parser\n**lines**"), mds);
+    assert_eq!(parse("# Hello World!\nrust parser\n**lines**"), Ok(mds));
 }
 #[test]
 fn test_table() {
     let a = words!(normal_word!("A"));
     let b = words!(normal_word!("B"));
     let c = words!(normal_word!("C"));
-    let header = Record(vec!(a, b, c));
+    let header = Record::new(vec!(a, b, c));
     let align = vec!(Align::Right, Align::Left, Align::Center);
     let d = words!(normal_word!("d"));
     let e = words!(normal_word!("e"));
     let f = words!(normal_word!("f"));
-    let record0 = Record(vec!(d, e, f));
+    let record0 = Record::new(vec!(d, e, f));
     let j = words!(normal_word!("j"));
     let k = words!(normal_word!("k"));
     let l = words!(normal_word!("l"));
-    let record1 = Record(vec!(j, k, l));
+    let record1 = Record::new(vec!(j, k, l));
     let records = vec!(record0, record1);
-    let md = Md::Table(Box::new(Table{header, align, records}));
+    let md = Md::Table(Box::new(Table{header, align, records, span: Span::default()}));
     let test_word = "| A | B | C | \n|-:|--|:-:|\n| d | e | f |\n| j | k | l |\n";
-    assert_eq!(parse(&test_word), vec!(md));
+    assert_eq!(parse(test_word), Ok(vec!(md)));
+
+    let mds = parse("| a | b |\n|---|---|\n| c | d |\n| x |\nafter").unwrap();
+    assert_eq!(mds.len(), 3);
+    assert_eq!(mds[1], Md::Sentence(words!(normal_word!("| x |"))));
+    assert_eq!(mds[2], Md::Sentence(words!(normal_word!("after"))));
+}
+
+fn leaf(text: &str) -> Item {
+    Item::new(vec!(Md::Sentence(words!(normal_word!(text)))), None)
 }
 
 #[test]
 fn test_list() {
-    let world = words!(normal_word!("World"));
-    let item0 = Item(world, items!());
-    let children = items!(item0);
-    let hello = words!(normal_word!("Hello"));
-    let item = Item(hello, children);
-    let md = Md::List(items!(item));
-    assert_eq!(parse(&"- Hello\n  - World"), vec!(md));
-}
\ No newline at end of file
+    let children = List { items: items!(leaf("World")), loose: false, span: Span::default() };
+    let hello = Md::Sentence(words!(normal_word!("Hello")));
+    let item = Item::new(vec!(hello, Md::List(children)), None);
+    let md = Md::List(List { items: items!(item), loose: false, span: Span::default() });
+    assert_eq!(parse("- Hello\n  - World"), Ok(vec!(md)));
+
+    let md = Md::List(List { items: items!(leaf("Hello"), leaf("World")), loose: true, span: Span::default() });
+    let end = Md::Sentence(words!(normal_word!("End")));
+    assert_eq!(parse("- Hello\n\n- World\nEnd"), Ok(vec!(md, end)));
+}
+
+#[test]
+fn test_list_indent() {
+    let children = List { items: items!(leaf("World")), loose: false, span: Span::default() };
+    let hello = Md::Sentence(words!(normal_word!("Hello")));
+    let item = Item::new(vec!(hello, Md::List(children)), None);
+    let md = Md::List(List { items: items!(item), loose: false, span: Span::default() });
+    assert_eq!(parse("- Hello\n\t- World"), Ok(vec!(md)));
+
+    let opts = ParserOptions { list_indent: ListIndent::FourSpaces, ..ParserOptions::default() };
+    let md = Md::List(List { items: items!(leaf("Hello"), leaf("World")), loose: false, span: Span::default() });
+    assert_eq!(parse_with("- Hello\n  - World", &opts), Ok(vec!(md)));
+}
+
+#[test]
+fn test_heading_ids() {
+    let heading = |level: usize, text: &str, id: &str| Md::Heading(Heading {
+        level,
+        words: words!(normal_word!(text)),
+        id: id.to_string(),
+        attrs: Attributes::default(),
+        span: Span::default(),
+    });
+    let mds = vec!(
+        heading(1, "Setup", "setup"),
+        heading(2, "Setup", "setup-1"),
+        heading(2, "Setup", "custom"),
+        heading(3, "Setup", "setup-2"),
+    );
+    assert_eq!(parse("# Setup\n## Setup\n## Setup {#custom}\n### Setup"), Ok(mds));
+}
+
+#[test]
+fn test_toc() {
+    use app::parser::toc::{toc, TocEntry, TocOptions};
+
+    let mds = parse("[TOC]\n# Intro\n## Usage").unwrap();
+    assert_eq!(mds[0], Md::Toc(Span::default()));
+    let usage = TocEntry { level: 2, id: "usage".to_string(), text: "Usage".to_string(), children: vec!() };
+    let intro = TocEntry { level: 1, id: "intro".to_string(), text: "Intro".to_string(), children: vec!(usage) };
+    assert_eq!(toc(&mds, &TocOptions::default()), vec!(intro));
+}
+
+#[test]
+fn test_emoji() {
+    let opts = ParserOptions { emoji: true, ..ParserOptions::default() };
+    let rocket = Word::Emoji { shortcode: "rocket".to_string(), char: "🚀".to_string() };
+    let code = Word::Code(":rocket:".to_string());
+    let words = words!(normal_word!("Launch "), rocket, normal_word!(" "), code, normal_word!(" :nope:"));
+    let md = Md::Sentence(words);
+    assert_eq!(parse_with("Launch :rocket: `:rocket:` :nope:", &opts), Ok(vec!(md)));
+
+    let md = Md::Sentence(words!(normal_word!("Launch :rocket:")));
+    assert_eq!(parse("Launch :rocket:"), Ok(vec!(md)));
+}
+
+#[test]
+fn test_admonition() {
+    let body = vec!(Md::Sentence(words!(normal_word!("Back up first."))));
+    let title = words!(normal_word!("Before you start"));
+    let md = Md::Admonition { kind: AdmonitionKind::Warning, title: Some(title), body, span: Span::default() };
+    let end = Md::Sentence(words!(normal_word!("Done")));
+    assert_eq!(parse("> [!WARNING] Before you start\n> Back up first.\nDone"), Ok(vec!(md, end)));
+}
+
+#[test]
+fn test_wiki_links() {
+    let opts = ParserOptions { wiki_links: true, ..ParserOptions::default() };
+    let link = Word::WikiLink { page: "Getting Started".to_string(), label: Some("start here".to_string()) };
+    let md = Md::Sentence(words!(normal_word!("See "), link));
+    let mds = parse_with("See [[Getting Started|start here]]", &opts).unwrap();
+    assert_eq!(mds, vec!(md));
+    assert_eq!(app::parser::wiki::pages(&mds), vec!("Getting Started".to_string()));
+}
+
+#[test]
+fn test_attributes() {
+    let cls = |class: &str| Attributes { id: None, classes: vec!(class.to_string()), pairs: vec!() };
+
+    let heading = Md::Heading(Heading {
+        level: 1,
+        words: words!(normal_word!("Title")),
+        id: "top".to_string(),
+        attrs: cls("wide"),
+        span: Span::default(),
+    });
+    let code = Md::Code(CodeBlock { lang: "rust".to_string(), code: "let x = 1;\n".to_string(), attrs: cls("numbered"), span: Span::default() });
+    let link = Word::Link { words: words!(normal_word!("docs")), url: "/docs".to_string(), title: None, attrs: cls("ext") };
+    let span = Word::Span { words: words!(normal_word!("note")), attrs: cls("aside") };
+    let sentence = Md::Sentence(words!(link, normal_word!(" and "), span));
+    let text = "# Title {#top .wide}\n```rust {.numbered}\nlet x = 1;\n```\n[docs](/docs){.ext} and [note]{.aside}";
+    assert_eq!(parse(text), Ok(vec!(heading, code, sentence)));
+}
+
+#[test]
+fn test_abbreviations() {
+    let opts = ParserOptions { abbreviations: true, ..ParserOptions::default() };
+    let abbr = || Word::Abbreviation { abbr: "HTML".to_string(), title: "Hyper Text Markup Language".to_string() };
+    let sentence = Md::Sentence(words!(normal_word!("The "), abbr(), normal_word!(" spec")));
+    let item = Md::Sentence(words!(abbr(), normal_word!(" and XHTML")));
+    let list = Md::List(List { items: items!(Item::new(vec!(item), None)), loose: false, span: Span::default() });
+    let text = "*[HTML]: Hyper Text Markup Language\nThe HTML spec\n- HTML and XHTML";
+    assert_eq!(parse_with(text, &opts), Ok(vec!(sentence, list)));
+}
+
+struct Repo;
+
+impl app::parser::reference::ReferenceResolver for Repo {
+    fn resolve(&self, reference: &app::parser::reference::Reference) -> Option<String> {
+        match reference {
+            app::parser::reference::Reference::Issue { repo: None, number } => Some(format!("/issues/{}", number)),
+            _ => None,
+        }
+    }
+}
+
+#[test]
+fn test_references() {
+    let mut mds = parse("Fixes #7, not `#8` or https://x.y/#9").unwrap();
+    app::parser::reference::references(&mut mds, &Repo);
+    let attrs = Attributes { classes: vec!("issue".to_string()), ..Attributes::default() };
+    let link = Word::Link { words: words!(normal_word!("#7")), url: "/issues/7".to_string(), title: None, attrs };
+    let code = Word::Code("#8".to_string());
+    let md = Md::Sentence(words!(normal_word!("Fixes "), link, normal_word!(", not "), code, normal_word!(" or https://x.y/#9")));
+    assert_eq!(mds, vec!(md));
+}
+
+#[test]
+fn test_smart_punctuation() {
+    let opts = ParserOptions { smart_punctuation: true, ..ParserOptions::default() };
+    let bold = Word::Bold(words!(normal_word!("Rust")));
+    let code = Word::Code("a -- 'b'".to_string());
+    let md = Md::Sentence(words!(
+        normal_word!("\u{201C}"), bold, normal_word!("\u{201D} isn\u{2019}t "), code, normal_word!(" \u{2014} really\u{2026}")
+    ));
+    assert_eq!(parse_with("\"**Rust**\" isn't `a -- 'b'` --- really...", &opts), Ok(vec!(md)));
+}
+
+#[test]
+fn test_containers() {
+    let cls = Attributes { id: None, classes: vec!("wide".to_string()), pairs: vec!() };
+    let quote = Md::Quote(vec!(Md::Sentence(words!(normal_word!("quoted")))), Span::default());
+    let inner = Md::Container { name: "tip".to_string(), attrs: Attributes::default(), body: vec!(quote), span: Span::default() };
+    let text = Md::Sentence(words!(normal_word!("text")));
+    let outer = Md::Container { name: "warning".to_string(), attrs: cls, body: vec!(text, inner), span: Span::default() };
+    let end = Md::Sentence(words!(normal_word!("end")));
+    assert_eq!(parse(":::: warning {.wide}\ntext\n::: tip\n> quoted\n:::\n::::\nend"), Ok(vec!(outer, end)));
+}
+
+#[test]
+fn test_details() {
+    let code = Md::Code(CodeBlock { lang: "sh".to_string(), code: "reboot\n".to_string(), attrs: Attributes::default(), span: Span::default() });
+    let details = Md::Details { summary: words!(normal_word!("It hangs")), open: false, body: vec!(code), span: Span::default() };
+    let end = Md::Sentence(words!(normal_word!("end")));
+    assert_eq!(parse("??? It hangs\n    ```sh\n    reboot\n    ```\nend"), Ok(vec!(details, end)));
+}
+
+#[test]
+fn test_citations() {
+    let opts = ParserOptions { citations: true, ..ParserOptions::default() };
+    let cite = |key: &str, locator: Option<&str>| Cite { key: key.to_string(), locator: locator.map(|l| l.to_string()), suppress_author: false };
+    let textual = Word::Citation { cites: vec!(cite("knuth1984", None)), in_text: true };
+    let bracketed = Word::Citation { cites: vec!(cite("lamport94", Some("p. 4"))), in_text: false };
+    let md = Md::Sentence(words!(
+        normal_word!("As "), textual, normal_word!(" notes "), bracketed, normal_word!(", mail a@b.org.")
+    ));
+    let mds = parse_with("As @knuth1984 notes [@lamport94, p. 4], mail a@b.org.", &opts).unwrap();
+    assert_eq!(mds, vec!(md));
+
+    let entries = app::parser::bibtex::bibtex("@book{knuth1984, author = {Donald Knuth}, year = 1984}");
+    assert_eq!(app::parser::citation::warnings(&mds, &entries), vec!("unknown citation key: lamport94".to_string()));
+}
+
+struct Files;
+
+impl app::parser::include::FileSystem for Files {
+    fn read(&self, path: &std::path::Path) -> std::io::Result<String> {
+        match path.to_str() {
+            Some("book/index.md") => Ok("# Book\n{{#include chapters/one.md}}".to_string()),
+            Some("book/chapters/one.md") => Ok("==marked==".to_string()),
+            Some("loop.md") => Ok("{{#include loop.md}}".to_string()),
+            _ => Err(std::io::Error::from(std::io::ErrorKind::NotFound)),
+        }
+    }
+}
+
+#[test]
+fn test_include() {
+    use app::parser::include::{parse_file, IncludeError};
+    use std::path::Path;
+
+    let opts = ParserOptions { highlight: true, ..ParserOptions::default() };
+    let heading = Md::Heading(Heading { level: 1, words: words!(normal_word!("Book")), id: "book".to_string(), attrs: Attributes::default(), span: Span::default() });
+    let marked = Md::Sentence(words!(Word::Highlight(words!(normal_word!("marked")))));
+    assert_eq!(parse_file(Path::new("book/index.md"), &Files, &opts).unwrap().without_spans(), vec!(heading, marked));
+    assert!(matches!(parse_file(Path::new("loop.md"), &Files, &opts), Err(IncludeError::Cycle(_))));
+}
+
+#[test]
+fn test_variables() {
+    use app::parser::variables::{parse_with_variables, VariableOptions, VariableError};
+
+    let vars = VariableOptions {
+        config: vec!(("product".to_string(), "App".to_string()), ("version".to_string(), "0.1".to_string())),
+        defines: vec!(("version".to_string(), "2.0".to_string())),
+        strict: false,
+    };
+    let text = "---\nproduct: Crate\n---\n# {{ product }} {{ version }}\nRun `{{ version }}` {{ unset }}";
+    let heading = Md::Heading(Heading { level: 1, words: words!(normal_word!("Crate 2.0")), id: "crate-20".to_string(), attrs: Attributes::default(), span: Span::default() });
+    let sentence = Md::Sentence(words!(normal_word!("Run "), Word::Code("{{ version }}".to_string()), normal_word!(" {{ unset }}")));
+    assert_eq!(parse_with_variables(text, &ParserOptions::default(), &vars).without_spans(), Ok(vec!(heading, sentence)));
+
+    let strict = VariableOptions { strict: true, ..vars };
+    assert_eq!(parse_with_variables(text, &ParserOptions::default(), &strict), Err(VariableError::Undefined("unset".to_string())));
+}
+
+#[test]
+fn test_no_panics() {
+    let opts = ParserOptions {
+        highlight: true, superscript: true, subscript: true, emoji: true, wiki_links: true,
+        abbreviations: true, smart_punctuation: true, citations: true, ..ParserOptions::default()
+    };
+    let pieces = [
+        "*", "_", "~", "^", "=", "`", "[", "]", "(", ")", "{", "}", "!", "@", "#", ":", "|", "-", "+",
+        ">", "<", "\"", "'", "?", ".", "\\", " ", "    ", "\t", "\n", "a", "1.", "é", "日本", "😀",
+        "```", ":::", "???", "[!NOTE]", "<details>", "</details>", "*[", "[[", "{#", "{{", "http://",
+    ];
+    for text in ["|", " | ", ">|", "- |", "> - |", "::: a\n|\n:::", "| a |\n|"] {
+        assert!(parse_with(text, &opts).is_ok(), "{:?}", text);
+    }
+    let mut seed: u64 = 42;
+    for _ in 0..2000 {
+        let mut text = String::new();
+        for _ in 0..(seed % 40) {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            text.push_str(pieces[(seed >> 33) as usize % pieces.len()]);
+        }
+        if let Err(error) = parse_with(&text, &opts) {
+            assert!(error.line >= 1 && error.column >= 1, "{:?}: {}", text, error);
+        }
+    }
+}
+
+#[test]
+fn test_positions() {
+    let text = "# Title\n| A | **B** |\n|--|--|\n| c | d |\n- one\n  > quoted\n  - two\nend";
+    let mds = app::parser::parser::parse(text).unwrap();
+    let range = |span: Span| &text[span.range()];
+    assert_eq!(range(mds[0].span()), "# Title");
+    assert_eq!(range(mds[1].span()), "| A | **B** |\n|--|--|\n| c | d |");
+    assert_eq!(mds[1].span().end, Point { offset: 39, line: 4, column: 10 });
+
+    let Md::Table(table) = &mds[1] else { panic!("expected a table") };
+    let Word::Bold(bold) = &table.header.0[1].0[0] else { panic!("expected bold") };
+    assert_eq!(range(table.header.0[1].1[0]), "**B**");
+    assert_eq!(range(bold.1[0]), "B");
+    assert_eq!(table.records[0].0[1].1[0].start, Point { offset: 36, line: 4, column: 7 });
+
+    let Md::List(list) = &mds[2] else { panic!("expected a list") };
+    let item = &list.items.0[0].0;
+    assert_eq!(range(mds[2].span()), "- one\n  > quoted\n  - two");
+    assert_eq!(range(item[0].span()), "one");
+    assert_eq!(range(item[1].span()), "> quoted");
+    assert_eq!(range(item[1].children()[0][0].span()), "quoted");
+    assert_eq!(item[2].span().start, Point { offset: 59, line: 7, column: 3 });
+    assert_eq!(range(mds[3].span()), "end");
+    assert_eq!(range(list.items.0[0].2), "- one\n  > quoted\n  - two");
+    assert_eq!(range(table.header.1), "| A | **B** |");
+    assert_eq!(table.records[0].1.start, Point { offset: 30, line: 4, column: 1 });
+
+    let text = "a [bc](u) and [*x*]{.y}";
+    let opts = ParserOptions { attributes: true, ..ParserOptions::default() };
+    let mds = app::parser::parser::parse_with(text, &opts).unwrap();
+    let range = |span: Span| &text[span.range()];
+    let Md::Sentence(words) = &mds[0] else { panic!("expected a sentence") };
+    let Word::Link { words: link, .. } = &words.0[1] else { panic!("expected a link") };
+    assert_eq!(range(words.1[1]), "[bc](u)");
+    assert_eq!(range(link.1[0]), "bc");
+    let Word::Span { words: span, .. } = &words.0[3] else { panic!("expected a span") };
+    let Word::Italic(italic) = &span.0[0] else { panic!("expected italic") };
+    assert_eq!(range(span.1[0]), "*x*");
+    assert_eq!(range(italic.1[0]), "x");
+}
+
+#[test]
+fn test_smartened_positions() {
+    let text = "go :rocket: now... \"x\"";
+    let opts = ParserOptions { emoji: true, smart_punctuation: true, ..ParserOptions::default() };
+    let mds = app::parser::parser::parse_with(text, &opts).unwrap();
+    let Md::Sentence(words) = &mds[0] else { panic!("expected a sentence") };
+    let ranges: Vec<&str> = words.1.iter().map(|span| &text[span.range()]).collect();
+    assert_eq!(ranges, ["go ", ":rocket:", " now... \"x\""]);
+    assert_eq!(words.1[2].start.column, 12);
+}
+
+#[test]
+fn test_nested_positions() {
+    let text = "- a\n- b\n  - c\n  - d\n    | x |\n    |---|\n    | y |\n- e";
+    let mds = app::parser::parser::parse(text).unwrap();
+    let range = |span: Span| &text[span.range()];
+    let Md::List(list) = &mds[0] else { panic!("expected a list") };
+    let items: Vec<&str> = list.items.0.iter().map(|item| range(item.2)).collect();
+    assert_eq!(items, ["- a", "- b\n  - c\n  - d\n    | x |\n    |---|\n    | y |", "- e"]);
+    assert_eq!(range(list.items.0[2].0[0].span()), "e");
+
+    let Md::List(nested) = &list.items.0[1].0[1] else { panic!("expected a nested list") };
+    let d = &nested.items.0[1];
+    assert_eq!(range(d.2), "- d\n    | x |\n    |---|\n    | y |");
+    assert_eq!(range(d.0[0].span()), "d");
+    let Md::Table(table) = &d.0[1] else { panic!("expected a table") };
+    assert_eq!((range(table.header.1), range(table.records[0].1)), ("| x |", "| y |"));
+    assert_eq!(table.records[0].1.start, Point { offset: 44, line: 7, column: 5 });
+    assert_eq!(range(table.records[0].0[0].1[0]), "y");
+}
+
+/// `:: text` lines, with the text parsed as nested blocks.
+struct Margin;
+
+impl BlockParser for Margin {
+    fn name(&self) -> &str {
+        "margin"
+    }
+
+    fn priority(&self) -> u32 {
+        65
+    }
+
+    fn parse<'a>(&self, text: &'a str, opts: &ParserOptions) -> Option<ParsedResult<'a, Md>> {
+        let (line, rest) = split_first_pattern(text, "\n");
+        let inner = consume(line, ":: ")?;
+        let body = parse_lines(text, &[inner], opts).ok()?;
+        Some(ParsedResult::new(Md::Container { name: "margin".to_string(), attrs: Attributes::default(), body, span: Span::default() }, rest))
+    }
+}
+
+#[test]
+fn test_block_parser() {
+    let text = "- item\n  :: # Note\n:: plain";
+    let mds = Parser::default().block(Margin).parse(text).unwrap();
+    let Md::List(list) = &mds[0] else { panic!("expected a list") };
+    let margin = &list.items.0[0].0[1];
+    assert!(matches!(margin, Md::Container { name, body, .. } if name == "margin" && matches!(body[0], Md::Heading(_))));
+    assert_eq!(&text[margin.span().range()], ":: # Note");
+    assert_eq!(&text[margin.children()[0][0].span().range()], "# Note");
+    assert!(matches!(&mds[1], Md::Container { name, .. } if name == "margin"));
+
+    let mds = Parser::default().block(Margin).disable("list").unwrap().parse("- item").unwrap();
+    assert_eq!(mds.without_spans(), vec!(Md::Sentence(words!(normal_word!("- item")))));
+}
+
+/// `:name:` marks a keyboard key; unknown names stay plain.
+struct Key;
+
+impl InlineParser for Key {
+    fn triggers(&self) -> Vec<&str> {
+        vec!(":")
+    }
+
+    fn parse<'a>(&self, text: &'a str, _opts: &ParserOptions) -> Option<ParsedResult<'a, Word>> {
+        let (key, rest) = split_first_pattern(consume(text, ":")?, ":");
+        if !["ctrl", "alt"].contains(&key) { return None }
+        Some(ParsedResult::new(Word::Extension { name: "kbd".to_string(), words: words!(normal_word!(key)) }, rest))
+    }
+}
+
+#[test]
+fn test_inline_parser() {
+    let parser = Parser::default().inline(Delimited::new("spoiler", "||", "||")).inline(Key);
+    let spoiler = Word::Extension { name: "spoiler".to_string(), words: words!(normal_word!("it ends")) };
+    let key = Word::Extension { name: "kbd".to_string(), words: words!(normal_word!("ctrl")) };
+    let md = Md::Sentence(words!(normal_word!("Press "), key, normal_word!(" "), normal_word!(":"), normal_word!("x"), normal_word!(":"), normal_word!(" "), spoiler));
+    assert_eq!(parser.parse("Press :ctrl: :x: ||it ends||").without_spans(), Ok(vec!(md)));
+}
+
+#[test]
+fn test_profiles() {
+    let text = "__a__ ~~b~~\n| A |\n|---|\n| a |\n- [x] done\n[TOC]";
+    let plain = |text: &str| Md::Sentence(words!(normal_word!(text)));
+
+    let mds = parse_with(text, &ParserOptions::commonmark()).unwrap();
+    let bold = Word::Bold(words!(normal_word!("a")));
+    assert_eq!(mds[0], Md::Sentence(words!(bold, normal_word!(" ~~b~~"))));
+    assert_eq!(mds[1..4], [plain("| A |"), plain("|---|"), plain("| a |")]);
+    assert!(matches!(&mds[4], Md::List(list) if list.items.0[0].1.is_none()));
+    assert!(matches!(mds[5], Md::Sentence(_)));
+
+    let mds = parse_with(text, &ParserOptions::gfm()).unwrap();
+    let Md::Sentence(words) = &mds[0] else { panic!("expected a sentence") };
+    assert!(matches!(words.0[..], [Word::Bold(_), _, Word::StrikeThough(_)]));
+    assert!(matches!(&mds[1], Md::Table(_)));
+    assert!(matches!(&mds[2], Md::List(list) if list.items.0[0].1 == Some(true)));
+
+    let mds = parse_with(text, &ParserOptions::legacy()).unwrap();
+    let Md::Sentence(words) = &mds[0] else { panic!("expected a sentence") };
+    assert!(matches!(words.0[0], Word::Underline(_)));
+    assert!(matches!(&mds[2], Md::List(list) if list.items.0[0].1.is_none()));
+    assert_eq!(mds[3], Md::Toc(Span::default()));
+    assert_eq!(parse(text), Ok(mds));
+
+    let opts = ParserOptions { footnotes: false, ..ParserOptions::gfm() };
+    assert!(matches!(parse_with("[^1]: note", &opts).unwrap()[..], [Md::Sentence(_)]));
+    assert!(matches!(parse_with("[^1]: note", &ParserOptions::gfm()).unwrap()[..], [Md::Footnote { .. }]));
+}
@@ -0,0 +1,403 @@
+//! Reads the examples of a CommonMark-style spec and reports how many of
+//! them the crate renders as expected.
+//!
+//! The vendored specs keep their upstream licenses, given in their front
+//! matter.
+
+// Each suite uses only part of this module.
+#![allow(dead_code)]
+
+use std::panic;
+use std::sync::Arc;
+use std::thread;
+use app::parser::parser::ParseError;
+
+pub struct Example {
+    pub number: usize,
+    pub section: String,
+    /// The example's extension, as in `example table`, if any.
+    pub extension: Option<String>,
+    pub markdown: String,
+    pub html: String,
+}
+
+const FENCE: &str = "````````````````````````````````";
+
+/// The examples of `spec`, each under the last heading before it.
+pub fn examples(spec: &str) -> Vec<Example> {
+    let mut examples: Vec<Example> = vec!();
+    let mut section = String::new();
+    let mut lines = spec.lines().take_while(|line| *line != "<!-- END TESTS -->");
+    while let Some(line) = lines.next() {
+        if let Some(heading) = heading(line) {
+            section = heading.to_string();
+            continue
+        }
+        let Some(kind) = line.strip_prefix(FENCE).and_then(|rest| rest.strip_prefix(" example")) else { continue };
+        let extension = Some(kind.trim()).filter(|kind| !kind.is_empty()).map(str::to_string);
+        let mut markdown = String::new();
+        let mut html = String::new();
+        let mut in_html = false;
+        for line in lines.by_ref() {
+            if line == FENCE { break }
+            match (line, in_html) {
+                (".", false) => in_html = true,
+                (line, false) => markdown.push_str(&format!("{}\n", line)),
+                (line, true) => html.push_str(&format!("{}\n", line)),
+            }
+        }
+        examples.push(Example {
+            number: examples.len() + 1,
+            section: section.clone(),
+            extension,
+            markdown: markdown.replace('→', "\t"),
+            html: html.replace('→', "\t"),
+        });
+    }
+    examples
+}
+
+fn heading(line: &str) -> Option<&str> {
+    let hashes = line.len() - line.trim_start_matches('#').len();
+    if hashes == 0 { return None }
+    line[hashes..].strip_prefix(' ')
+}
+
+const BLOCK_TAGS: [&str; 50] = [
+    "article", "header", "aside", "hgroup", "blockquote", "hr", "iframe", "body", "li", "map",
+    "button", "object", "canvas", "ol", "caption", "output", "col", "p", "colgroup", "pre",
+    "dd", "progress", "div", "section", "dl", "table", "td", "dt", "tbody", "embed",
+    "textarea", "fieldset", "tfoot", "figcaption", "th", "figure", "thead", "footer", "tr", "form",
+    "ul", "h1", "h2", "h3", "h4", "h5", "h6", "video", "script", "style",
+];
+
+#[derive(PartialEq, Clone, Copy)]
+enum Last {
+    StartTag,
+    EndTag,
+    Data,
+    Other,
+}
+
+/// Normalizes HTML the way the spec's `normalize.py` does, so output that
+/// differs only in insignificant whitespace, attribute order, self-closing
+/// slashes or character references compares equal.
+pub fn normalize(html: &str) -> String {
+    let mut normalizer = Normalizer { output: String::new(), last: Last::StartTag, last_tag: String::new(), in_pre: false };
+    let mut rest = html;
+    while !rest.is_empty() {
+        let end = if rest.starts_with("<![CDATA[") {
+            rest.find("]]>").map_or(rest.len(), |n| n + 3)
+        } else if rest.starts_with('<') {
+            rest.find('>').map_or(rest.len(), |n| n + 1)
+        } else {
+            rest.find('<').unwrap_or(rest.len())
+        };
+        let (chunk, next) = rest.split_at(end.max(1).min(rest.len()));
+        normalizer.chunk(chunk);
+        rest = next;
+    }
+    normalizer.output
+}
+
+struct Normalizer {
+    output: String,
+    last: Last,
+    last_tag: String,
+    in_pre: bool,
+}
+
+impl Normalizer {
+    fn chunk(&mut self, chunk: &str) {
+        if chunk.starts_with("<!") || chunk.starts_with("<?") {
+            self.output.push_str(chunk);
+            self.last = Last::Other;
+        } else if let Some(name) = chunk.strip_prefix("</").and_then(|rest| rest.strip_suffix('>')).filter(|name| is_name(name.trim())) {
+            self.end_tag(&name.trim().to_lowercase());
+        } else if let Some((name, attrs, closed)) = start_tag(chunk) {
+            self.start_tag(&name, attrs);
+            if closed {
+                self.last = Last::EndTag;
+            }
+        } else {
+            self.text(chunk);
+        }
+    }
+
+    fn end_tag(&mut self, name: &str) {
+        if name == "pre" {
+            self.in_pre = false;
+        } else if BLOCK_TAGS.contains(&name) {
+            self.output.truncate(self.output.trim_end().len());
+        }
+        self.output.push_str(&format!("</{}>", name));
+        self.last_tag = name.to_string();
+        self.last = Last::EndTag;
+    }
+
+    fn start_tag(&mut self, name: &str, mut attrs: Vec<(String, Option<String>)>) {
+        if name == "pre" {
+            self.in_pre = true;
+        }
+        if BLOCK_TAGS.contains(&name) {
+            self.output.truncate(self.output.trim_end().len());
+        }
+        self.output.push('<');
+        self.output.push_str(name);
+        attrs.sort();
+        for (key, value) in attrs {
+            self.output.push(' ');
+            self.output.push_str(&key);
+            if let Some(value) = value {
+                self.output.push_str(&format!("=\"{}\"", escape(&unescape(&value))));
+            }
+        }
+        self.output.push('>');
+        self.last_tag = name.to_string();
+        self.last = Last::StartTag;
+    }
+
+    /// Text and the character references in it, handled in turn.
+    fn text(&mut self, text: &str) {
+        let mut rest = text;
+        while !rest.is_empty() {
+            match reference(rest) {
+                Some((c, len)) => {
+                    self.output.push_str(&escape(&c));
+                    self.last = Last::Other;
+                    rest = &rest[len..];
+                },
+                None => {
+                    let end = rest.char_indices().skip(1).find(|(_, c)| *c == '&').map_or(rest.len(), |(n, _)| n);
+                    self.data(&rest[..end]);
+                    rest = &rest[end..];
+                },
+            }
+        }
+    }
+
+    fn data(&mut self, data: &str) {
+        let after_tag = matches!(self.last, Last::StartTag | Last::EndTag);
+        let after_block_tag = after_tag && BLOCK_TAGS.contains(&self.last_tag.as_str());
+        let mut data = data.to_string();
+        if after_tag && self.last_tag == "br" {
+            data = data.trim_start_matches('\n').to_string();
+        }
+        if !self.in_pre {
+            data = collapse(&data);
+        }
+        if after_block_tag && !self.in_pre {
+            data = match self.last {
+                Last::StartTag => data.trim_start().to_string(),
+                _ => data.trim().to_string(),
+            };
+        }
+        self.output.push_str(&data);
+        self.last = Last::Data;
+    }
+}
+
+/// `data` with each run of whitespace as one space.
+fn collapse(data: &str) -> String {
+    let mut output = String::new();
+    for c in data.chars() {
+        if !c.is_whitespace() {
+            output.push(c);
+        } else if !output.ends_with(' ') {
+            output.push(' ');
+        }
+    }
+    output
+}
+
+fn is_name(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic()) && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+type Attrs = Vec<(String, Option<String>)>;
+
+/// A start tag's name, attributes and whether it closes itself.
+fn start_tag(chunk: &str) -> Option<(String, Attrs, bool)> {
+    let inner = chunk.strip_prefix('<')?.strip_suffix('>')?;
+    let (inner, closed) = match inner.strip_suffix('/') {
+        Some(inner) => (inner, true),
+        None => (inner, false),
+    };
+    let name_len = inner.find(|c: char| c.is_whitespace()).unwrap_or(inner.len());
+    let name = &inner[..name_len];
+    if !is_name(name) { return None }
+    let mut attrs: Attrs = vec!();
+    let mut rest = inner[name_len..].trim_start();
+    while !rest.is_empty() {
+        let key_len = rest.find(|c: char| c.is_whitespace() || c == '=').unwrap_or(rest.len());
+        if key_len == 0 { return None }
+        let key = rest[..key_len].to_lowercase();
+        rest = rest[key_len..].trim_start();
+        let Some(value) = rest.strip_prefix('=') else {
+            attrs.push((key, None));
+            continue
+        };
+        let value = value.trim_start();
+        let (value, next) = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => {
+                let end = value[1..].find(quote)? + 1;
+                (&value[1..end], &value[(end+1)..])
+            },
+            _ => value.split_at(value.find(char::is_whitespace).unwrap_or(value.len())),
+        };
+        attrs.push((key, Some(value.to_string())));
+        rest = next.trim_start();
+    }
+    Some((name.to_lowercase(), attrs, closed))
+}
+
+/// Named references whose characters appear in the spec examples; others
+/// are kept as written, as `normalize.py` keeps those it does not know.
+const ENTITIES: [(&str, &str); 12] = [
+    ("amp", "&"), ("lt", "<"), ("gt", ">"), ("quot", "\""), ("nbsp", "\u{a0}"), ("copy", "©"),
+    ("AElig", "Æ"), ("frac34", "¾"), ("ouml", "ö"), ("auml", "ä"), ("uuml", "ü"), ("eacute", "é"),
+];
+
+/// The character of a leading `&name;`, `&#n;` or `&#xn;`, and its length.
+fn reference(text: &str) -> Option<(String, usize)> {
+    let body = text.strip_prefix('&')?;
+    let end = body.find(';')?;
+    let name = &body[..end];
+    let c = match name.strip_prefix('#') {
+        Some(number) => {
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code).filter(|c| *c != '\0').unwrap_or('\u{fffd}').to_string()
+        },
+        None => ENTITIES.iter().find(|(entity, _)| *entity == name)?.1.to_string(),
+    };
+    Some((c, end + 2))
+}
+
+fn unescape(text: &str) -> String {
+    let mut output = String::new();
+    let mut rest = text;
+    while let Some(n) = rest.find('&') {
+        output.push_str(&rest[..n]);
+        rest = &rest[n..];
+        match reference(rest) {
+            Some((c, len)) => {
+                output.push_str(&c);
+                rest = &rest[len..];
+            },
+            None => {
+                output.push('&');
+                rest = &rest[1..];
+            },
+        }
+    }
+    output + rest
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+enum Outcome {
+    Pass,
+    Fail,
+    /// The parser rejected the example, which CommonMark never does.
+    Error,
+    /// The renderer panicked, which the crate should never do.
+    Panic,
+}
+
+/// What `run` found: how many examples passed, and the numbers of those
+/// that failed to parse or panicked.
+pub struct Report {
+    pub passed: usize,
+    pub errors: Vec<usize>,
+    pub panics: Vec<usize>,
+}
+
+/// Renders every example with `render` and compares it with the expected
+/// HTML, printing the pass rate of each section. Set `SPEC_EXAMPLE` to a
+/// number to also print that example's expected and actual HTML.
+pub fn run(title: &str, examples: &[Example], render: &dyn Fn(&str) -> Result<String, ParseError>) -> Report {
+    let mut sections: Vec<(String, usize, usize)> = vec!();
+    let mut report = Report { passed: 0, errors: vec!(), panics: vec!() };
+    let shown: Option<usize> = std::env::var("SPEC_EXAMPLE").ok().and_then(|n| n.parse().ok());
+    // Tests run side by side, so only this thread's panics are silenced.
+    let hook = Arc::new(panic::take_hook());
+    let previous = hook.clone();
+    let runner = thread::current().id();
+    panic::set_hook(Box::new(move |info| if thread::current().id() != runner { previous(info) }));
+    for example in examples {
+        let result = panic::catch_unwind(panic::AssertUnwindSafe(|| render(&example.markdown)));
+        let outcome = match &result {
+            Ok(Ok(html)) if normalize(html) == normalize(&example.html) => Outcome::Pass,
+            Ok(Ok(_)) => Outcome::Fail,
+            Ok(Err(_)) => Outcome::Error,
+            Err(_) => Outcome::Panic,
+        };
+        if shown == Some(example.number) {
+            let html = result.as_ref().map(|html| html.as_ref().map(|html| normalize(html)));
+            println!("{}\n---\n{}\n---\n{:?}\n", example.markdown, normalize(&example.html), html);
+        }
+        match outcome {
+            Outcome::Error => report.errors.push(example.number),
+            Outcome::Panic => report.panics.push(example.number),
+            _ => (),
+        }
+        if sections.last().is_none_or(|(section, _, _)| *section != example.section) {
+            sections.push((example.section.clone(), 0, 0));
+        }
+        let section = sections.last_mut().unwrap();
+        section.1 += matches!(outcome, Outcome::Pass) as usize;
+        section.2 += 1;
+    }
+    drop(panic::take_hook());
+    if let Some(hook) = Arc::into_inner(hook) {
+        panic::set_hook(hook);
+    }
+
+    report.passed = sections.iter().map(|(_, passed, _)| passed).sum();
+    println!("{}: {}/{} examples pass ({:.1}%)", title, report.passed, examples.len(), percent(report.passed, examples.len()));
+    for (section, passed, total) in sections.iter() {
+        println!("  {:<40} {:>3}/{:<3} {:>5.1}%", section, passed, total, percent(*passed, *total));
+    }
+    report
+}
+
+fn percent(passed: usize, total: usize) -> f64 {
+    if total == 0 { return 0.0 }
+    100.0 * passed as f64 / total as f64
+}
+
+/// The text of each cell in each row of the first table in `html`, or
+/// `None` without one. This leaves out the markup renderers may differ in,
+/// such as `<thead>` and alignment, to compare what a table parser found.
+pub fn table_rows(html: &str) -> Option<Vec<Vec<String>>> {
+    let html = normalize(html);
+    let start = html.find("<table")?;
+    let end = html[start..].find("</table>").map_or(html.len(), |n| start + n);
+    let rows = html[start..end].split("<tr").skip(1).map(|row| {
+        let row = &row[..row.find("</tr>").unwrap_or(row.len())];
+        row.split("<t").skip(1)
+            .filter(|cell| cell.starts_with(['h', 'd']))
+            .map(|cell| {
+                let cell = &cell[(cell.find('>').map_or(cell.len(), |n| n + 1))..];
+                strip_tags(&cell[..cell.find("</t").unwrap_or(cell.len())])
+            })
+            .collect()
+    });
+    Some(rows.collect())
+}
+
+fn strip_tags(html: &str) -> String {
+    let mut text = String::new();
+    let mut rest = html;
+    while let Some(n) = rest.find('<') {
+        text.push_str(&rest[..n]);
+        rest = &rest[(rest[n..].find('>').map_or(rest.len(), |m| n + m + 1))..];
+    }
+    text.push_str(rest);
+    text.trim().to_string()
+}